@@ -1,5 +1,6 @@
 use darling::{
     ast::{Data, Fields},
+    util::Flag,
     FromDeriveInput, FromField, FromVariant,
 };
 use proc_macro::TokenStream;
@@ -12,11 +13,16 @@ struct ItemOpts {
     ident: syn::Ident,
     data: Data<VariantOpts, FieldOpts>,
     table: u32,
+    column_family: Option<String>,
+    version: Option<u32>,
+    upgrade_from: Option<syn::Path>,
+    hooks: Flag,
 }
 
 #[derive(FromVariant)]
 #[darling(attributes(solid))]
 struct VariantOpts {
+    ident: syn::Ident,
     fields: Fields<FieldOpts>,
 }
 
@@ -25,10 +31,8 @@ struct VariantOpts {
 struct FieldOpts {
     ident: Option<syn::Ident>,
     ty: syn::Type,
-    #[darling(default)]
-    unique: bool,
-    #[darling(default)]
-    indexed: bool,
+    unique: Option<bool>,
+    indexed: Option<bool>,
 }
 
 #[derive(FromDeriveInput)]
@@ -36,20 +40,94 @@ struct FieldOpts {
 struct SingleOpts {
     ident: syn::Ident,
     single: u32,
+    version: Option<u32>,
+}
+
+#[derive(FromDeriveInput)]
+#[darling(supports(enum_unit, struct_newtype))]
+struct IndexValueOpts {
+    ident: syn::Ident,
+    data: Data<UnitVariantOpts, FieldOpts>,
+}
+
+#[derive(FromVariant)]
+struct UnitVariantOpts {
+    ident: syn::Ident,
+}
+
+#[proc_macro_derive(IndexValue)]
+pub fn derive_index_value(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input);
+    let IndexValueOpts { ident, data } = match IndexValueOpts::from_derive_input(&input) {
+        Ok(v) => v,
+        Err(e) => return TokenStream::from(e.write_errors()),
+    };
+
+    let (body, element) = match data {
+        Data::Struct(_) => (
+            quote! {
+                ::soliddb::IndexValue::as_bytes(&self.0)
+            },
+            quote! {
+                ::soliddb::IndexValue::append_element(&self.0, bytes)
+            },
+        ),
+        Data::Enum(variants) => {
+            let names = variants.iter().map(|variant| &variant.ident);
+            let positions = (0u32..).take(variants.len());
+            let body = quote! {
+                let position = match self {
+                    #(Self::#names => #positions,)*
+                };
+                position.to_be_bytes().to_vec()
+            };
+            // positions have a fixed length
+            let element = quote! {
+                bytes.extend(::soliddb::IndexValue::as_bytes(self))
+            };
+            (body, element)
+        }
+    };
+
+    let output = quote! {
+        impl ::soliddb::IndexValue for #ident {
+            fn as_bytes(&self) -> Vec<u8> {
+                #body
+            }
+
+            fn append_element(&self, bytes: &mut Vec<u8>) {
+                #element
+            }
+        }
+    };
+    output.into()
 }
 
 #[proc_macro_derive(Single, attributes(solid))]
 pub fn derive_single(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input);
-    let SingleOpts { ident, single } = match SingleOpts::from_derive_input(&input) {
+    let SingleOpts {
+        ident,
+        single,
+        version,
+    } = match SingleOpts::from_derive_input(&input) {
         Ok(v) => v,
         Err(e) => return TokenStream::from(e.write_errors()),
     };
 
+    // keys of these singles share the prefix of the change feed and the catalog
+    if single >= 0xFE00_0000 {
+        panic!("singles from {} are reserved", 0xFE00_0000u32);
+    }
+
+    let registration = registration("Single", &ident, single, version, quote! { None });
     let output = quote! {
         impl ::soliddb::Single for #ident {
             const SINGLE: u32 = #single;
+            const NAME: &'static str = stringify!(#ident);
         }
+
+        #registration
     };
     output.into()
 }
@@ -57,7 +135,15 @@ pub fn derive_single(input: TokenStream) -> TokenStream {
 #[proc_macro_derive(Table, attributes(solid))]
 pub fn derive_item(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input);
-    let ItemOpts { ident, table, data } = match ItemOpts::from_derive_input(&input) {
+    let ItemOpts {
+        ident,
+        table,
+        data,
+        column_family,
+        version,
+        upgrade_from,
+        hooks,
+    } = match ItemOpts::from_derive_input(&input) {
         Ok(v) => v,
         Err(e) => return TokenStream::from(e.write_errors()),
     };
@@ -66,13 +152,154 @@ pub fn derive_item(input: TokenStream) -> TokenStream {
         panic!("table 0 is reserved");
     }
 
-    match data {
-        Data::Struct(fields) => gen_struct(ident, table, fields),
-        Data::Enum(variants) => gen_enum(ident, table, variants),
+    let fingerprint = fingerprint(&data);
+    let column_family = column_family.map(|name| {
+        quote! {
+            const COLUMN_FAMILY: Option<&'static str> = Some(#name);
+        }
+    });
+    let upgrade = upgrade_from.map(|old| {
+        quote! {
+            fn upgrade(bytes: &[u8]) -> Option<::soliddb::Result<Self>> {
+                let old = <#old as ::soliddb::Table>::decode(bytes);
+                Some(old.map(|(old, _)| <Self as ::std::convert::From<#old>>::from(old)))
+            }
+
+            fn upgrade_index_keys(
+                bytes: &[u8],
+                id: ::soliddb::ulid::Ulid,
+            ) -> Option<::soliddb::Result<Vec<Vec<u8>>>> {
+                Some(<#old as ::soliddb::Table>::stored_index_keys(bytes, id))
+            }
+        }
+    });
+    let hooks = hooks.is_present().then(|| {
+        quote! {
+            fn before_create_hook(
+                &self,
+                ctx: &mut ::soliddb::WriteContext<'_>,
+            ) -> ::soliddb::Result<Option<Self>> {
+                let mut value = ::std::clone::Clone::clone(self);
+                <Self as ::soliddb::Hooks>::before_create(&mut value, ctx)?;
+                Ok(Some(value))
+            }
+
+            fn after_create_hook(&self, id: ::soliddb::ulid::Ulid) {
+                <Self as ::soliddb::Hooks>::after_create(self, id)
+            }
+
+            fn before_update_hook(
+                &self,
+                old: &::soliddb::WithId<Self>,
+                ctx: &mut ::soliddb::WriteContext<'_>,
+            ) -> ::soliddb::Result<Option<Self>> {
+                let mut value = ::std::clone::Clone::clone(self);
+                <Self as ::soliddb::Hooks>::before_update(&mut value, old, ctx)?;
+                Ok(Some(value))
+            }
+
+            fn after_update_hook(&self, old: &::soliddb::WithId<Self>) {
+                <Self as ::soliddb::Hooks>::after_update(self, old)
+            }
+
+            fn before_delete_hook(
+                item: &::soliddb::WithId<Self>,
+                ctx: &mut ::soliddb::WriteContext<'_>,
+            ) -> ::soliddb::Result<()> {
+                <Self as ::soliddb::Hooks>::before_delete(item, ctx)
+            }
+
+            fn after_delete_hook(item: &::soliddb::WithId<Self>) {
+                <Self as ::soliddb::Hooks>::after_delete(item)
+            }
+        }
+    });
+    let items = quote! {
+        const FINGERPRINT: u64 = #fingerprint;
+        #column_family
+        #hooks
+        #upgrade
+    };
+
+    let info = quote! { Some(::soliddb::TableInfo::registered::<#ident>) };
+    let registration = registration("Table", &ident, table, version, info);
+    let output: proc_macro2::TokenStream = match data {
+        Data::Struct(fields) => gen_struct(ident, table, items, fields),
+        Data::Enum(variants) => gen_enum(ident, table, items, variants),
+    }
+    .into();
+
+    quote! {
+        #output
+        #registration
+    }
+    .into()
+}
+
+/// Registers the claimed number, checked when opening a database.
+///
+/// The exported marker macro makes two types of one crate claiming
+/// the same number without a version, or the same version of it, fail to compile.
+fn registration(
+    claim: &str,
+    ident: &syn::Ident,
+    id: u32,
+    version: Option<u32>,
+    info: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let (marker, version) = match version {
+        Some(version) => (
+            format_ident!("__soliddb_{}_{id}_version_{version}", claim.to_lowercase()),
+            quote! { Some(#version) },
+        ),
+        None => (
+            format_ident!("__soliddb_{}_{id}", claim.to_lowercase()),
+            quote! { None },
+        ),
+    };
+    let marker = quote! {
+        #[doc(hidden)]
+        #[macro_export]
+        #[allow(unknown_lints, non_local_definitions)]
+        macro_rules! #marker {
+            () => {};
+        }
+    };
+    let (rebuild, dump) = match claim {
+        "Table" => (
+            quote! { Some(<#ident as ::soliddb::Table>::rebuild_indexes) },
+            quote! { ::soliddb::DumpFns::table::<#ident>() },
+        ),
+        _ => (
+            quote! { None },
+            quote! { ::soliddb::DumpFns::single::<#ident>() },
+        ),
+    };
+    let claim = format_ident!("{claim}");
+
+    quote! {
+        ::soliddb::inventory::submit! {
+            ::soliddb::Registration {
+                claim: ::soliddb::Claim::#claim,
+                id: #id,
+                version: #version,
+                path: concat!(module_path!(), "::", stringify!(#ident)),
+                info: #info,
+                rebuild: #rebuild,
+                dump: #dump,
+            }
+        }
+
+        #marker
     }
 }
 
-fn gen_struct(ident: syn::Ident, table: u32, fields: Fields<FieldOpts>) -> TokenStream {
+fn gen_struct(
+    ident: syn::Ident,
+    table: u32,
+    items: proc_macro2::TokenStream,
+    fields: Fields<FieldOpts>,
+) -> TokenStream {
     let unique_fields = find_unique_fields(&fields);
     let unique_field_names: Vec<_> = unique_fields
         .iter()
@@ -119,10 +346,10 @@ fn gen_struct(ident: syn::Ident, table: u32, fields: Fields<FieldOpts>) -> Token
         quote! {}
     } else {
         quote! {
-            fn unique_value(&self, index: u8) -> Vec<u8> {
+            fn unique_value(&self, index: u8) -> ::soliddb::Result<Vec<u8>> {
                 match index {
-                    #(#unique_keys => ::soliddb::IndexValue::as_bytes(&self.#unique_field_names),)*
-                    _ => unreachable!("no unique value for index {}", index),
+                    #(#unique_keys => Ok(::soliddb::IndexValue::as_bytes(&self.#unique_field_names)),)*
+                    _ => Err(::soliddb::Error::UnknownIndex { table: Self::NAME, index }),
                 }
             }
         }
@@ -132,10 +359,33 @@ fn gen_struct(ident: syn::Ident, table: u32, fields: Fields<FieldOpts>) -> Token
         quote! {}
     } else {
         quote! {
-            fn non_unique_value(&self, index: u8) -> Vec<u8> {
+            fn non_unique_value(&self, index: u8) -> ::soliddb::Result<Vec<u8>> {
+                match index {
+                    #(#indexed_keys => Ok(::soliddb::IndexValue::as_bytes(&self.#indexed_field_names)),)*
+                    _ => Err(::soliddb::Error::UnknownIndex { table: Self::NAME, index }),
+                }
+            }
+        }
+    };
+
+    let field_names = fields
+        .iter()
+        .filter_map(|field| field.ident.as_ref())
+        .map(|name| name.to_string());
+
+    let index_name_func = if unique_keys.is_empty() && indexed_keys.is_empty() {
+        quote! {}
+    } else {
+        let keys = unique_keys.iter().chain(indexed_keys.iter());
+        let names = unique_field_names
+            .iter()
+            .chain(indexed_field_names.iter())
+            .map(|name| name.to_string());
+        quote! {
+            fn index_name(index: u8) -> Option<&'static str> {
                 match index {
-                    #(#indexed_keys => ::soliddb::IndexValue::as_bytes(&self.#indexed_field_names),)*
-                    _ => unreachable!("no non unique value for index {}", index),
+                    #(#keys => Some(#names),)*
+                    _ => None,
                 }
             }
         }
@@ -144,11 +394,15 @@ fn gen_struct(ident: syn::Ident, table: u32, fields: Fields<FieldOpts>) -> Token
     let output = quote! {
         impl ::soliddb::Table for #ident {
             const TABLE: u32 = #table;
+            const NAME: &'static str = stringify!(#ident);
             const UNIQUE_INDICES: &'static [u8] = &[#(#unique_keys),*];
             const NON_UNIQUE_INDICES: &'static [u8] = &[#(#indexed_keys),*];
+            const FIELDS: &'static [&'static str] = &[#(#field_names),*];
+            #items
 
             #unique_value_func
             #non_unique_value_func
+            #index_name_func
         }
 
         impl #ident {
@@ -159,8 +413,13 @@ fn gen_struct(ident: syn::Ident, table: u32, fields: Fields<FieldOpts>) -> Token
     output.into()
 }
 
-fn gen_enum(ident: syn::Ident, table: u32, variants: Vec<VariantOpts>) -> TokenStream {
-    for variant in variants {
+fn gen_enum(
+    ident: syn::Ident,
+    table: u32,
+    items: proc_macro2::TokenStream,
+    variants: Vec<VariantOpts>,
+) -> TokenStream {
+    for variant in &variants {
         if !find_unique_fields(&variant.fields).is_empty() {
             panic!("unique fields are not allowed for enums");
         }
@@ -170,38 +429,99 @@ fn gen_enum(ident: syn::Ident, table: u32, variants: Vec<VariantOpts>) -> TokenS
         }
     }
 
+    let variant_names = variants.iter().map(|variant| variant.ident.to_string());
+
     let output = quote! {
         impl ::soliddb::Table for #ident {
             const TABLE: u32 = #table;
+            const NAME: &'static str = stringify!(#ident);
+            const FIELDS: &'static [&'static str] = &[#(#variant_names),*];
+            #items
         }
     };
     output.into()
 }
 
+/// Hashes the fields with their types and index attributes using FNV-1a,
+/// which is stable across compiler versions.
+fn fingerprint(data: &Data<VariantOpts, FieldOpts>) -> u64 {
+    let describe = |fields: &Fields<FieldOpts>| -> Vec<String> {
+        fields
+            .iter()
+            .enumerate()
+            .map(|(position, field)| {
+                let name = match &field.ident {
+                    Some(ident) => ident.to_string(),
+                    None => position.to_string(),
+                };
+                let ty = &field.ty;
+                let unique = if field.unique.unwrap_or_default() {
+                    " unique"
+                } else {
+                    ""
+                };
+                let indexed = if field.indexed.unwrap_or_default() {
+                    " indexed"
+                } else {
+                    ""
+                };
+                format!("{name}: {}{unique}{indexed}", quote!(#ty))
+            })
+            .collect()
+    };
+
+    let layout = match data {
+        Data::Struct(fields) => format!("struct {{{}}}", describe(fields).join(", ")),
+        Data::Enum(variants) => {
+            let variants: Vec<_> = variants
+                .iter()
+                .map(|variant| {
+                    format!(
+                        "{}({})",
+                        variant.ident,
+                        describe(&variant.fields).join(", ")
+                    )
+                })
+                .collect();
+            format!("enum {{{}}}", variants.join(", "))
+        }
+    };
+
+    layout.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
 fn find_unique_fields(fields: &Fields<FieldOpts>) -> Vec<FieldOpts> {
     fields
         .iter()
+        .filter(|field| field.unique.unwrap_or_default())
         .cloned()
-        .filter(|field| field.unique)
         .collect()
 }
 
 fn find_indexed_fields(fields: &Fields<FieldOpts>) -> Vec<FieldOpts> {
     fields
         .iter()
+        .filter(|field| field.indexed.unwrap_or_default())
         .cloned()
-        .filter(|field| field.indexed)
         .collect()
 }
 
 fn unique_getter_method(index: u8, field: &syn::Ident, ty: &syn::Type) -> proc_macro2::TokenStream {
     let method = format_ident!("get_by_{field}");
+    let exists_method = format_ident!("exists_by_{field}");
 
     quote! {
-        pub fn #method(db: &::soliddb::DB, value: &#ty) -> ::soliddb::Result<::soliddb::WithId<Self>> {
+        pub fn #method<M: ::soliddb::Mode>(db: &::soliddb::Database<M>, value: &#ty) -> ::soliddb::Result<::soliddb::WithId<Self>> {
             let value = <#ty as ::soliddb::IndexValue>::as_bytes(value);
             Self::get_by_unique_index(db, #index, &value)
         }
+
+        pub fn #exists_method<M: ::soliddb::Mode>(db: &::soliddb::Database<M>, value: &#ty) -> ::soliddb::Result<bool> {
+            let value = <#ty as ::soliddb::IndexValue>::as_bytes(value);
+            Self::exists_by_unique_index(db, #index, &value)
+        }
     }
 }
 
@@ -211,11 +531,35 @@ fn indexed_getter_method(
     ty: &syn::Type,
 ) -> proc_macro2::TokenStream {
     let method = format_ident!("get_by_{field}");
+    let iter_method = format_ident!("iter_by_{field}");
+    let ids_method = format_ident!("ids_by_{field}");
+    let count_method = format_ident!("count_by_{field}");
+    let exists_method = format_ident!("exists_by_{field}");
 
     quote! {
-        pub fn #method(db: &::soliddb::DB, value: &#ty) -> ::soliddb::Result<Vec<::soliddb::WithId<Self>>> {
+        pub fn #method<M: ::soliddb::Mode>(db: &::soliddb::Database<M>, value: &#ty) -> ::soliddb::Result<Vec<::soliddb::WithId<Self>>> {
             let value = <#ty as ::soliddb::IndexValue>::as_bytes(value);
             Self::get_by_non_unique_index(db, #index, &value)
         }
+
+        pub fn #iter_method<'a, M: ::soliddb::Mode>(db: &'a ::soliddb::Database<M>, value: &#ty) -> ::soliddb::IndexedItems<'a, Self, M> {
+            let value = <#ty as ::soliddb::IndexValue>::as_bytes(value);
+            Self::iter_by_non_unique_index(db, #index, &value)
+        }
+
+        pub fn #ids_method<'a, M: ::soliddb::Mode>(db: &'a ::soliddb::Database<M>, value: &#ty) -> ::soliddb::Ids<'a> {
+            let value = <#ty as ::soliddb::IndexValue>::as_bytes(value);
+            Self::ids_by_non_unique_index(db, #index, &value)
+        }
+
+        pub fn #count_method<M: ::soliddb::Mode>(db: &::soliddb::Database<M>, value: &#ty) -> ::soliddb::Result<usize> {
+            let value = <#ty as ::soliddb::IndexValue>::as_bytes(value);
+            Self::count_by_non_unique_index(db, #index, &value)
+        }
+
+        pub fn #exists_method<M: ::soliddb::Mode>(db: &::soliddb::Database<M>, value: &#ty) -> ::soliddb::Result<bool> {
+            let value = <#ty as ::soliddb::IndexValue>::as_bytes(value);
+            Self::exists_by_non_unique_index(db, #index, &value)
+        }
     }
 }
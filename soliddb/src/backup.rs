@@ -0,0 +1,106 @@
+use std::path::Path;
+
+use rocksdb::backup::{BackupEngine, BackupEngineInfo, BackupEngineOptions, RestoreOptions};
+use rocksdb::Env;
+
+use crate::{Database, Error, Result};
+
+/// Description of a backup stored in a backup directory.
+/// Returned by [list_backups] and [Database::backup_to].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Backup {
+    /// Id of the backup, increasing with every backup.
+    pub id: u32,
+
+    /// Creation time in seconds since the unix epoch.
+    pub timestamp: i64,
+
+    /// Size in bytes including files shared with other backups.
+    pub size: u64,
+
+    /// Number of files belonging to the backup.
+    pub files: u32,
+}
+
+impl Database {
+    /// Creates a new backup of the database in the given directory
+    /// while it stays available for reads and writes.
+    ///
+    /// Backups in the same directory are incremental, files already
+    /// stored by a previous backup are shared instead of copied.
+    pub fn backup_to<P: AsRef<Path>>(&self, dir: P) -> Result<Backup> {
+        let mut engine = open_engine(dir.as_ref())?;
+        engine.create_new_backup_flush(self, true)?;
+
+        backups(&engine).pop().ok_or_else(|| Error::Unexpected {
+            reason: format!(
+                "backup in {} is missing after creating it",
+                dir.as_ref().display()
+            ),
+        })
+    }
+}
+
+/// Returns all backups stored in the given directory, oldest first.
+pub fn list_backups<P: AsRef<Path>>(dir: P) -> Result<Vec<Backup>> {
+    let engine = open_engine(dir.as_ref())?;
+    Ok(backups(&engine))
+}
+
+/// Deletes all but the latest `keep` backups in the given directory.
+pub fn purge_old_backups<P: AsRef<Path>>(dir: P, keep: usize) -> Result<()> {
+    let mut engine = open_engine(dir.as_ref())?;
+    engine.purge_old_backups(keep)?;
+    Ok(())
+}
+
+/// Restores the latest backup in `backup_dir` into `target_dir`,
+/// which can then be opened with [open](crate::open).
+///
+/// The target must not be opened while restoring.
+pub fn restore_from<P: AsRef<Path>, Q: AsRef<Path>>(backup_dir: P, target_dir: Q) -> Result<()> {
+    let mut engine = open_engine(backup_dir.as_ref())?;
+    let target = target_dir.as_ref();
+    engine.restore_from_latest_backup(target, target, &RestoreOptions::default())?;
+    Ok(())
+}
+
+/// Restores the backup with the given id in `backup_dir` into `target_dir`.
+/// See [restore_from].
+pub fn restore_backup<P: AsRef<Path>, Q: AsRef<Path>>(
+    backup_dir: P,
+    id: u32,
+    target_dir: Q,
+) -> Result<()> {
+    let mut engine = open_engine(backup_dir.as_ref())?;
+    let target = target_dir.as_ref();
+    engine.restore_from_backup(target, target, &RestoreOptions::default(), id)?;
+    Ok(())
+}
+
+fn open_engine(dir: &Path) -> Result<BackupEngine> {
+    let options = BackupEngineOptions::new(dir)?;
+    let engine = BackupEngine::open(&options, &Env::new()?)?;
+    Ok(engine)
+}
+
+fn backups(engine: &BackupEngine) -> Vec<Backup> {
+    let mut backups: Vec<_> = engine
+        .get_backup_info()
+        .into_iter()
+        .map(Backup::from)
+        .collect();
+    backups.sort_by_key(|backup| backup.id);
+    backups
+}
+
+impl From<BackupEngineInfo> for Backup {
+    fn from(info: BackupEngineInfo) -> Self {
+        Self {
+            id: info.backup_id,
+            timestamp: info.timestamp,
+            size: info.size,
+            files: info.num_files,
+        }
+    }
+}
@@ -0,0 +1,268 @@
+use rocksdb::{Direction, IteratorMode};
+use serde::{Deserialize, Serialize};
+
+use crate::iter::read_options;
+use crate::keys::{catalog_key, catalog_prefix, Key};
+use crate::table::{from_bytes, to_bytes, CODEC};
+use crate::{registry, Database, Mode, OpenOptions, ReadWrite, Registration, Result, Table};
+
+/// Metadata of a table as recorded in the catalog of a database.
+///
+/// Tables are recorded when first written, see [Database::catalog].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TableInfo {
+    /// Number of the table.
+    pub table: u32,
+
+    /// Name of the type.
+    pub name: String,
+
+    /// Column family holding the values and indices.
+    pub column_family: String,
+
+    /// Names of the fields of a struct, or the variants of an enum.
+    pub fields: Vec<String>,
+
+    /// Unique and non-unique indices.
+    pub indices: Vec<IndexInfo>,
+
+    /// Encoding of the stored values.
+    pub codec: String,
+
+    /// Hash of the field layout and indices, see [Table::FINGERPRINT].
+    /// Zero for entries recorded without a fingerprint.
+    #[serde(default)]
+    pub fingerprint: u64,
+}
+
+impl TableInfo {
+    /// Returns the metadata of the given table as stored by the given database.
+    pub fn of<T: Table, M: Mode>(db: &Database<M>) -> Self {
+        Self::registered::<T>(db.config())
+    }
+
+    /// Returns the metadata of the given table as stored with the given options.
+    #[doc(hidden)]
+    pub fn registered<T: Table>(options: &OpenOptions) -> Self {
+        let indices = T::UNIQUE_INDICES
+            .iter()
+            .chain(T::NON_UNIQUE_INDICES)
+            .map(|&index| IndexInfo {
+                index,
+                field: T::index_name(index).map(str::to_string),
+                unique: index < 128,
+            })
+            .collect();
+
+        Self {
+            table: T::TABLE,
+            name: T::NAME.to_string(),
+            column_family: options.column_family::<T>().into_owned(),
+            fields: T::FIELDS.iter().map(|field| field.to_string()).collect(),
+            indices,
+            codec: CODEC.to_string(),
+            fingerprint: T::FINGERPRINT,
+        }
+    }
+
+    /// Returns the name of the field the given index is derived from.
+    pub fn index_name(&self, index: u8) -> Option<&str> {
+        self.indices
+            .iter()
+            .find(|info| info.index == index)
+            .and_then(|info| info.field.as_deref())
+    }
+}
+
+/// Differences between the catalog entry of a table and its current type.
+///
+/// Returned by [Database::schema_diff] and [SchemaDiff::between].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaDiff {
+    /// Number of the table.
+    pub table: u32,
+
+    /// Name of the current type.
+    pub name: String,
+
+    /// Fields or variants of the current type missing in the catalog.
+    pub added_fields: Vec<String>,
+
+    /// Fields or variants in the catalog missing in the current type.
+    pub removed_fields: Vec<String>,
+
+    /// Indices which have to be backfilled with [Table::rebuild_index].
+    pub added_indices: Vec<IndexInfo>,
+
+    /// Indices whose stale entries are only dropped by [Table::rebuild_indexes].
+    pub removed_indices: Vec<IndexInfo>,
+
+    /// Codec in the catalog if the current one differs.
+    pub changed_codec: Option<String>,
+
+    /// Whether the fingerprints differ, which is also
+    /// the case if only the type of a field changed.
+    pub changed_fingerprint: bool,
+}
+
+impl SchemaDiff {
+    /// Compares the catalog entry of a table with the current one,
+    /// returning `None` if they match.
+    pub fn between(stored: &TableInfo, current: &TableInfo) -> Option<Self> {
+        let diff = Self {
+            table: current.table,
+            name: current.name.clone(),
+            added_fields: missing(&current.fields, &stored.fields),
+            removed_fields: missing(&stored.fields, &current.fields),
+            added_indices: missing(&current.indices, &stored.indices),
+            removed_indices: missing(&stored.indices, &current.indices),
+            changed_codec: (stored.codec != current.codec).then(|| stored.codec.clone()),
+            changed_fingerprint: stored.fingerprint != 0
+                && stored.fingerprint != current.fingerprint,
+        };
+
+        (!diff.is_empty()).then_some(diff)
+    }
+
+    /// Returns true if nothing changed.
+    pub fn is_empty(&self) -> bool {
+        self.added_fields.is_empty()
+            && self.removed_fields.is_empty()
+            && self.added_indices.is_empty()
+            && self.removed_indices.is_empty()
+            && self.changed_codec.is_none()
+            && !self.changed_fingerprint
+    }
+}
+
+/// Returns the items of `items` not contained in `other`.
+fn missing<T: Clone + PartialEq>(items: &[T], other: &[T]) -> Vec<T> {
+    items
+        .iter()
+        .filter(|item| !other.contains(item))
+        .cloned()
+        .collect()
+}
+
+/// Metadata of an index of a table.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexInfo {
+    /// Number of the index.
+    pub index: u8,
+
+    /// Name of the field the index is derived from.
+    pub field: Option<String>,
+
+    /// Whether every value may only be stored once.
+    pub unique: bool,
+}
+
+impl<M: Mode> Database<M> {
+    /// Returns the metadata of all tables recorded in this database,
+    /// ordered by their number.
+    ///
+    /// Tables which were stored before the catalog existed are only
+    /// listed after they were written again.
+    pub fn catalog(&self) -> Result<Vec<TableInfo>> {
+        let prefix = catalog_prefix();
+        let mode = IteratorMode::From(&prefix, Direction::Forward);
+        let mut tables = Vec::new();
+        for item in self.iterator_opt(mode, read_options(&prefix)) {
+            let (key, bytes) = item?;
+            if let Ok(Key::Catalog { .. }) = Key::parse(&key) {
+                tables.push(from_bytes("TableInfo", &bytes)?);
+            }
+        }
+
+        Ok(tables)
+    }
+
+    /// Returns the metadata recorded for the table with the given number.
+    pub fn table_info(&self, table: u32) -> Result<Option<TableInfo>> {
+        let Some(bytes) = self.get_pinned(catalog_key(table))? else {
+            return Ok(None);
+        };
+        Ok(Some(from_bytes("TableInfo", &bytes)?))
+    }
+
+    /// Compares every table in the catalog of this database with the latest
+    /// version of its registered type, see [Registration](crate::Registration).
+    /// Tables without a registered type are skipped.
+    ///
+    /// Writes keep the catalog entry of a changed table, it is only replaced by
+    /// [Table::rebuild_indexes], [Table::upgrade_all] and [Database::accept_schema].
+    /// The differences found when opening are kept in [Database::schema_changes].
+    pub fn schema_diff(&self) -> Result<Vec<SchemaDiff>> {
+        let diffs = self.registered_diffs()?;
+        Ok(diffs.into_iter().map(|(diff, _)| diff).collect())
+    }
+
+    /// Returns the differences of the tables in the catalog with their registered type.
+    fn registered_diffs(&self) -> Result<Vec<(SchemaDiff, &'static Registration)>> {
+        let mut diffs = Vec::new();
+        for stored in self.catalog()? {
+            let Some(registration) = registry::table_of(self.config(), &stored) else {
+                continue;
+            };
+            let Some(info) = registration.info else {
+                continue;
+            };
+
+            if let Some(diff) = SchemaDiff::between(&stored, &info(self.config())) {
+                diffs.push((diff, registration));
+            }
+        }
+
+        Ok(diffs)
+    }
+
+    /// Records the given table in the catalog unless an entry exists.
+    ///
+    /// An existing entry is only replaced if its schema matches.
+    pub(crate) fn record<T: Table>(&self) -> Result<()> {
+        let info = TableInfo::of::<T, M>(self);
+        let replace = match self.table_info(T::TABLE)? {
+            Some(stored) => stored != info && SchemaDiff::between(&stored, &info).is_none(),
+            None => true,
+        };
+        if replace {
+            let bytes = to_bytes("TableInfo", &info)?;
+            self.put_opt(catalog_key(T::TABLE), bytes, self.write_options())?;
+        }
+        Ok(())
+    }
+}
+
+impl Database {
+    /// Rebuilds the indices of every registered table whose indices differ from
+    /// its catalog entry, see [OpenOptions::rebuild_indexes](crate::OpenOptions::rebuild_indexes).
+    pub(crate) fn rebuild_changed_indexes(&self) -> Result<()> {
+        for (diff, registration) in self.registered_diffs()? {
+            if diff.added_indices.is_empty() && diff.removed_indices.is_empty() {
+                continue;
+            }
+            if let Some(rebuild) = registration.rebuild {
+                rebuild(self)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the catalog entry of the given table with its current schema.
+    ///
+    /// Call this once the stored values and indices match a changed table,
+    /// see [Database::schema_diff].
+    pub fn accept_schema<T: Table>(&self) -> Result<()> {
+        let bytes = to_bytes("TableInfo", &TableInfo::of::<T, ReadWrite>(self))?;
+        self.put(catalog_key(T::TABLE), bytes)?;
+        self.remember::<T>();
+        Ok(())
+    }
+
+    /// Removes the given table from the catalog.
+    pub(crate) fn unrecord<T: Table>(&self) -> Result<()> {
+        self.forget(T::TABLE);
+        self.delete(catalog_key(T::TABLE))
+    }
+}
@@ -0,0 +1,278 @@
+use std::sync::{Mutex, RwLock};
+
+use rocksdb::{DBIteratorWithThreadMode, Direction, IteratorMode, WriteBatch};
+use serde::{Deserialize, Serialize};
+use ulid::Ulid;
+
+use crate::iter::read_options;
+use crate::keys::{change_key, change_prefix, Key};
+use crate::table::{from_bytes, to_bytes};
+use crate::{Database, Mode, Result, DB};
+
+/// Kind of write recorded by a [ChangeEvent].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Op {
+    /// A value was stored for a new id, or a single was stored for the first time.
+    Create,
+    /// A stored value was replaced.
+    Update,
+    /// A stored value was deleted.
+    Delete,
+}
+
+/// Write recorded in the change feed, see [OpenOptions::change_feed](crate::OpenOptions::change_feed).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangeEvent {
+    /// Sequence number of the event, starting at 1.
+    pub seq: u64,
+    /// Number of the table of a changed row.
+    pub table: Option<u32>,
+    /// Number of a changed single.
+    pub single: Option<u32>,
+    /// Id of a changed row.
+    pub id: Option<Ulid>,
+    /// Kind of the write.
+    pub op: Op,
+    /// Encoded value before the write, missing for [Op::Create].
+    pub before: Option<Vec<u8>>,
+    /// Encoded value after the write, missing for [Op::Delete].
+    pub after: Option<Vec<u8>>,
+}
+
+/// Event as stored in the change feed and passed to subscribers.
+/// Values are RON and stored as text.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct Record {
+    pub(crate) table: Option<u32>,
+    pub(crate) single: Option<u32>,
+    pub(crate) id: Option<Ulid>,
+    pub(crate) op: Op,
+    pub(crate) before: Option<String>,
+    pub(crate) after: Option<String>,
+}
+
+impl Record {
+    pub(crate) fn table(table: u32, id: Ulid, before: Option<&[u8]>, after: Option<&[u8]>) -> Self {
+        Self::new(Some(table), None, Some(id), before, after)
+    }
+
+    pub(crate) fn single(single: u32, before: Option<&[u8]>, after: Option<&[u8]>) -> Self {
+        Self::new(None, Some(single), None, before, after)
+    }
+
+    fn new(
+        table: Option<u32>,
+        single: Option<u32>,
+        id: Option<Ulid>,
+        before: Option<&[u8]>,
+        after: Option<&[u8]>,
+    ) -> Self {
+        let op = match (before, after) {
+            (None, _) => Op::Create,
+            (Some(_), Some(_)) => Op::Update,
+            (Some(_), None) => Op::Delete,
+        };
+        let text = |bytes: &[u8]| String::from_utf8_lossy(bytes).into_owned();
+        Self {
+            table,
+            single,
+            id,
+            op,
+            before: before.map(text),
+            after: after.map(text),
+        }
+    }
+
+    fn into_event(self, seq: u64) -> ChangeEvent {
+        ChangeEvent {
+            seq,
+            table: self.table,
+            single: self.single,
+            id: self.id,
+            op: self.op,
+            before: self.before.map(String::into_bytes),
+            after: self.after.map(String::into_bytes),
+        }
+    }
+}
+
+/// Called with every recorded write, returning false once it is no longer interested.
+pub(crate) type Subscriber = Box<dyn FnMut(&Record) -> bool + Send>;
+
+/// State of the change feed and the subscribers of a handle.
+pub(crate) struct ChangeLog {
+    enabled: bool,
+    retention: Option<u64>,
+    /// Sequence number of the last event, read from the database on first use.
+    /// Held while writing recorded or subscribed writes so events are stored
+    /// and delivered in order.
+    last: Mutex<Option<u64>>,
+    subscribers: Mutex<Vec<Subscriber>>,
+    /// Shared by all writes of [Database::write_change],
+    /// held exclusively by [Database::write_exclusive].
+    writes: RwLock<()>,
+}
+
+impl ChangeLog {
+    pub(crate) fn new(enabled: bool, retention: Option<u64>) -> Self {
+        Self {
+            enabled,
+            retention,
+            last: Mutex::new(None),
+            subscribers: Mutex::default(),
+            writes: RwLock::default(),
+        }
+    }
+
+    pub(crate) fn subscribe(&self, subscriber: Subscriber) {
+        self.subscribers.lock().unwrap().push(subscriber);
+    }
+
+    fn has_subscribers(&self) -> bool {
+        !self.subscribers.lock().unwrap().is_empty()
+    }
+
+    fn notify(&self, record: &Record) {
+        self.subscribers
+            .lock()
+            .unwrap()
+            .retain_mut(|subscriber| subscriber(record));
+    }
+}
+
+/// Maximum number of writes between pruning events beyond the retention.
+const PRUNE_INTERVAL: u64 = 1024;
+
+impl<M: Mode> Database<M> {
+    /// Iterates over the events of the change feed with a sequence number
+    /// greater than `seq`, in the order they were written.
+    ///
+    /// Pass 0 to read all retained events, or the sequence number
+    /// of the last processed event to continue from there.
+    pub fn changes_since(&self, seq: u64) -> Changes<'_> {
+        let prefix = change_prefix();
+        let start = change_key(seq.saturating_add(1));
+        let mode = IteratorMode::From(&start, Direction::Forward);
+        Changes {
+            inner: (seq < u64::MAX).then(|| self.iterator_opt(mode, read_options(&prefix))),
+        }
+    }
+
+    /// Returns the sequence number of the last event of the change feed, 0 if there is none.
+    pub fn last_change(&self) -> Result<u64> {
+        let prefix = change_prefix();
+        let end = change_key(u64::MAX);
+        let mode = IteratorMode::From(&end, Direction::Reverse);
+        for item in self.iterator_opt(mode, read_options(&prefix)) {
+            let (key, _) = item?;
+            if !key.starts_with(&prefix) {
+                break;
+            }
+            if let Ok(Key::Change { seq }) = Key::parse(&key) {
+                return Ok(seq);
+            }
+        }
+
+        Ok(0)
+    }
+}
+
+impl Database {
+    /// Deletes all events of the change feed with a sequence number less than `seq`.
+    pub fn prune_changes(&self, seq: u64) -> Result<()> {
+        let mut batch = WriteBatch::default();
+        prune(&mut batch, seq);
+        self.write(batch)
+    }
+
+    /// Writes the batch and, if the change feed is enabled, the event returned
+    /// by `record` with the next sequence number. The event is passed to all
+    /// subscribers once written.
+    ///
+    /// `record` is called while holding the lock of the change feed,
+    /// so values it reads are not changed by other recorded writes.
+    /// Writes without the feed or subscribers only wait for [Database::write_exclusive].
+    pub(crate) fn write_change(
+        &self,
+        mut batch: WriteBatch,
+        record: impl FnOnce() -> Result<Option<Record>>,
+    ) -> Result<()> {
+        let log = self.change_log();
+        let _writes = log.writes.read().unwrap();
+        if !log.enabled && !log.has_subscribers() {
+            return self.write(batch);
+        }
+
+        let mut last = log.last.lock().unwrap();
+
+        let Some(record) = record()? else {
+            return self.write(batch);
+        };
+        if !log.enabled {
+            self.write(batch)?;
+            log.notify(&record);
+            return Ok(());
+        }
+
+        let seq = match *last {
+            Some(seq) => seq,
+            None => self.last_change()?,
+        } + 1;
+        batch.put(change_key(seq), to_bytes("ChangeEvent", &record)?);
+
+        if let Some(retention) = log.retention {
+            let interval = retention.clamp(1, PRUNE_INTERVAL);
+            if seq % interval == 0 && seq > retention {
+                prune(&mut batch, seq - retention + 1);
+            }
+        }
+
+        self.write(batch)?;
+        *last = Some(seq);
+        log.notify(&record);
+        Ok(())
+    }
+
+    /// Runs `write` while no [Database::write_change] is in progress,
+    /// so values it reads are not changed by the write methods of tables and singles.
+    pub(crate) fn write_exclusive<R>(&self, write: impl FnOnce() -> Result<R>) -> Result<R> {
+        let _writes = self.change_log().writes.write().unwrap();
+        write()
+    }
+}
+
+/// Adds the deletion of all events before `seq` to the batch.
+///
+/// No other keys share the prefix, since the singles using it are reserved.
+fn prune(batch: &mut WriteBatch, seq: u64) {
+    batch.delete_range(change_key(0), change_key(seq));
+}
+
+/// Iterator over the change feed returned by [Database::changes_since].
+pub struct Changes<'a> {
+    inner: Option<DBIteratorWithThreadMode<'a, DB>>,
+}
+
+impl Iterator for Changes<'_> {
+    type Item = Result<ChangeEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let prefix = change_prefix();
+        loop {
+            let (key, bytes) = match self.inner.as_mut()?.next()? {
+                Ok(item) => item,
+                Err(err) => return Some(Err(err.into())),
+            };
+            if !key.starts_with(&prefix) {
+                self.inner = None;
+                return None;
+            }
+            let Ok(Key::Change { seq }) = Key::parse(&key) else {
+                continue;
+            };
+
+            let record: Result<Record> = from_bytes("ChangeEvent", &bytes);
+            return Some(record.map(|record| record.into_event(seq)));
+        }
+    }
+}
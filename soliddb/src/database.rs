@@ -0,0 +1,617 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+use rocksdb::checkpoint::Checkpoint;
+use rocksdb::{
+    BlockBasedOptions, BoundColumnFamily, Cache, ColumnFamilyDescriptor, DBCompressionType,
+    DBWithThreadMode, MultiThreaded, Options, SliceTransform, WriteBatch, WriteOptions,
+    DEFAULT_COLUMN_FAMILY_NAME,
+};
+
+use crate::changes::ChangeLog;
+use crate::export::{Dump, DumpFns};
+use crate::keys::PREFIX_LEN;
+use crate::{registry, Error, Result, SchemaDiff, Table};
+
+/// The RocksDB instance wrapped by a [Database].
+pub type DB = DBWithThreadMode<MultiThreaded>;
+
+/// Handle to the column family holding the keys of a table.
+pub(crate) type Family<'a> = Arc<BoundColumnFamily<'a>>;
+
+mod private {
+    pub trait Sealed {}
+}
+
+/// Mode a [Database] was opened in. Write methods of
+/// [Table](crate::Table) and [Single](crate::Single) require [ReadWrite].
+pub trait Mode: private::Sealed + Sized {
+    #[doc(hidden)]
+    fn add_to_dump(fns: &DumpFns, dump: Dump<Self>) -> Dump<Self>;
+}
+
+/// Mode of a database opened with [open](crate::open).
+#[derive(Debug)]
+pub enum ReadWrite {}
+
+/// Mode of a database opened with [open_read_only](crate::open_read_only).
+///
+/// ```compile_fail
+/// # use serde::{Deserialize, Serialize};
+/// # use soliddb::Table;
+/// #[derive(Serialize, Deserialize, Table)]
+/// #[solid(table = 1)]
+/// struct User {
+///     name: String,
+/// }
+///
+/// let db = soliddb::open_read_only("/tmp/soliddb").unwrap();
+/// let user = User { name: "pako".to_string() };
+/// user.create(&db).unwrap();
+/// ```
+#[derive(Debug)]
+pub enum ReadOnly {}
+
+/// Mode of a database opened with [open_as_secondary](crate::open_as_secondary).
+#[derive(Debug)]
+pub enum Secondary {}
+
+impl private::Sealed for ReadWrite {}
+impl private::Sealed for ReadOnly {}
+impl private::Sealed for Secondary {}
+
+impl Mode for ReadWrite {
+    fn add_to_dump(fns: &DumpFns, dump: Dump<Self>) -> Dump<Self> {
+        (fns.read_write)(dump)
+    }
+}
+
+impl Mode for ReadOnly {
+    fn add_to_dump(fns: &DumpFns, dump: Dump<Self>) -> Dump<Self> {
+        (fns.read_only)(dump)
+    }
+}
+
+impl Mode for Secondary {
+    fn add_to_dump(fns: &DumpFns, dump: Dump<Self>) -> Dump<Self> {
+        (fns.secondary)(dump)
+    }
+}
+
+/// Handle to an open database. Accepted by all methods of
+/// [Table](crate::Table) and [Single](crate::Single).
+///
+/// Dereferences to the underlying [DB] for direct access. The write methods
+/// of [DB] stay reachable through [ReadOnly] and [Secondary] handles,
+/// where RocksDB rejects them with an error.
+pub struct Database<M: Mode = ReadWrite> {
+    db: DB,
+    options: Options,
+    write_options: WriteOptions,
+    config: OpenOptions,
+    /// Numbers and fingerprints of the tables recorded in the catalog by this handle.
+    recorded: RwLock<HashSet<(u32, u64)>>,
+    /// Differences of the catalog found when opening.
+    schema_changes: Vec<SchemaDiff>,
+    changes: ChangeLog,
+    _mode: PhantomData<M>,
+}
+
+impl Database {
+    /// Opens the database at the given path with default options.
+    /// See [OpenOptions] for tuning.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        OpenOptions::new().open(path)
+    }
+
+    /// Stores the given key-value pair using the configured write options.
+    pub fn put<K: AsRef<[u8]>, V: AsRef<[u8]>>(&self, key: K, value: V) -> Result<()> {
+        self.db.put_opt(key, value, &self.write_options)?;
+        Ok(())
+    }
+
+    /// Deletes the given key using the configured write options.
+    pub fn delete<K: AsRef<[u8]>>(&self, key: K) -> Result<()> {
+        self.db.delete_opt(key, &self.write_options)?;
+        Ok(())
+    }
+
+    /// Applies the given batch atomically using the configured write options.
+    pub fn write(&self, batch: WriteBatch) -> Result<()> {
+        self.db.write_opt(batch, &self.write_options)?;
+        Ok(())
+    }
+
+    /// Returns the column family of the given table, creating it if it does not exist.
+    ///
+    /// Records the table in the catalog on first use.
+    pub(crate) fn write_family<T: Table>(&self) -> Result<Family<'_>> {
+        let recorded = (T::TABLE, T::FINGERPRINT);
+        if !self.recorded.read().unwrap().contains(&recorded) {
+            self.record::<T>()?;
+            self.remember::<T>();
+        }
+
+        self.create_family(&self.column_family::<T>())
+    }
+
+    /// Skips recording the given table on further writes.
+    pub(crate) fn remember<T: Table>(&self) {
+        let recorded = (T::TABLE, T::FINGERPRINT);
+        self.recorded.write().unwrap().insert(recorded);
+    }
+
+    /// Returns the column family with the given name, creating it if it does not exist.
+    pub(crate) fn create_family(&self, name: &str) -> Result<Family<'_>> {
+        if let Some(family) = self.db.cf_handle(name) {
+            return Ok(family);
+        }
+
+        let options = self.config.family_options(&self.options, name);
+        let created = self.db.create_cf(name, &options);
+        // another thread may have created the column family in the meantime
+        match (self.db.cf_handle(name), created) {
+            (Some(family), _) => Ok(family),
+            (None, Err(err)) => Err(err.into()),
+            (None, Ok(())) => Err(Error::Unexpected {
+                reason: format!("column family {name} is missing after creating it"),
+            }),
+        }
+    }
+}
+
+impl<M: Mode> Database<M> {
+    /// Creates a consistent copy of the database in the given directory,
+    /// which must not exist yet. Table files are hard-linked where possible,
+    /// so checkpoints on the same filesystem are cheap.
+    pub fn checkpoint<P: AsRef<Path>>(&self, dir: P) -> Result<()> {
+        Checkpoint::new(&self.db)?.create_checkpoint(dir)?;
+        Ok(())
+    }
+
+    /// Creates a checkpoint in the given directory and opens it as
+    /// a separate database using the options of this database.
+    pub fn open_checkpoint<P: AsRef<Path>>(&self, dir: P) -> Result<Database> {
+        self.checkpoint(dir.as_ref())?;
+        self.config.open(dir)
+    }
+}
+
+impl Database<Secondary> {
+    /// Replays the changes written by the primary instance since the last call.
+    pub fn try_catch_up_with_primary(&self) -> Result<()> {
+        self.db.try_catch_up_with_primary()?;
+        Ok(())
+    }
+}
+
+impl<M: Mode> Database<M> {
+    /// Returns the name of the column family holding the given table.
+    ///
+    /// This is the `column_family` attribute of the table if set, otherwise
+    /// `table_<TABLE>` with [OpenOptions::column_family_per_table] and
+    /// the default column family without.
+    pub fn column_family<T: Table>(&self) -> Cow<'static, str> {
+        self.config.column_family::<T>()
+    }
+
+    /// Returns the tables which differed from their catalog entry when opening,
+    /// see [Database::schema_diff].
+    pub fn schema_changes(&self) -> &[SchemaDiff] {
+        &self.schema_changes
+    }
+
+    /// Returns the column family of the given table, `None` if it does not exist yet.
+    pub(crate) fn family<T: Table>(&self) -> Option<Family<'_>> {
+        self.db.cf_handle(&self.column_family::<T>())
+    }
+
+    pub(crate) fn config(&self) -> &OpenOptions {
+        &self.config
+    }
+
+    pub(crate) fn write_options(&self) -> &WriteOptions {
+        &self.write_options
+    }
+
+    pub(crate) fn change_log(&self) -> &ChangeLog {
+        &self.changes
+    }
+
+    /// Records the given table again on its next use.
+    pub(crate) fn forget(&self, table: u32) {
+        self.recorded
+            .write()
+            .unwrap()
+            .retain(|(recorded, _)| *recorded != table);
+    }
+}
+
+impl<M: Mode> std::fmt::Debug for Database<M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Database")
+            .field("path", &self.db.path())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<M: Mode> Deref for Database<M> {
+    type Target = DB;
+
+    fn deref(&self) -> &DB {
+        &self.db
+    }
+}
+
+/// Compression algorithm applied to stored blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Compression {
+    /// Store blocks uncompressed.
+    None,
+    /// Snappy compression, the RocksDB default.
+    Snappy,
+    /// Zlib compression.
+    Zlib,
+    /// Bzip2 compression.
+    Bz2,
+    /// LZ4 compression.
+    Lz4,
+    /// LZ4 high compression.
+    Lz4hc,
+    /// Zstandard compression.
+    Zstd,
+}
+
+impl From<Compression> for DBCompressionType {
+    fn from(compression: Compression) -> Self {
+        match compression {
+            Compression::None => DBCompressionType::None,
+            Compression::Snappy => DBCompressionType::Snappy,
+            Compression::Zlib => DBCompressionType::Zlib,
+            Compression::Bz2 => DBCompressionType::Bz2,
+            Compression::Lz4 => DBCompressionType::Lz4,
+            Compression::Lz4hc => DBCompressionType::Lz4hc,
+            Compression::Zstd => DBCompressionType::Zstd,
+        }
+    }
+}
+
+/// Options and flags used to configure how a [Database] is opened.
+///
+/// ```no_run
+/// # fn main() -> soliddb::Result<()> {
+/// let db = soliddb::OpenOptions::new()
+///     .cache_size(64 << 20)
+///     .compression(soliddb::Compression::Lz4)
+///     .open("/tmp/soliddb")?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct OpenOptions {
+    create_if_missing: bool,
+    cache_size: Option<usize>,
+    compression: Option<Compression>,
+    write_buffer_size: Option<usize>,
+    max_open_files: Option<i32>,
+    sync_writes: bool,
+    use_fsync: bool,
+    bloom_filter_bits: Option<f64>,
+    column_family_per_table: bool,
+    compression_per_family: HashMap<String, Compression>,
+    check_schema: bool,
+    check_collisions: bool,
+    rebuild_indexes: bool,
+    change_feed: bool,
+    change_retention: Option<u64>,
+}
+
+impl Default for OpenOptions {
+    fn default() -> Self {
+        Self {
+            create_if_missing: true,
+            cache_size: None,
+            compression: None,
+            write_buffer_size: None,
+            max_open_files: None,
+            sync_writes: false,
+            use_fsync: false,
+            bloom_filter_bits: Some(10.0),
+            column_family_per_table: false,
+            compression_per_family: HashMap::new(),
+            check_schema: false,
+            check_collisions: true,
+            rebuild_indexes: false,
+            change_feed: false,
+            change_retention: None,
+        }
+    }
+}
+
+impl OpenOptions {
+    /// Creates options matching [open](crate::open).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates the database and its directory if they do not exist. Defaults to true.
+    pub fn create_if_missing(&mut self, create: bool) -> &mut Self {
+        self.create_if_missing = create;
+        self
+    }
+
+    /// Size of the block cache in bytes.
+    pub fn cache_size(&mut self, bytes: usize) -> &mut Self {
+        self.cache_size = Some(bytes);
+        self
+    }
+
+    /// Compression used for stored blocks.
+    pub fn compression(&mut self, compression: Compression) -> &mut Self {
+        self.compression = Some(compression);
+        self
+    }
+
+    /// Amount of data in bytes buffered in memory before writing to disk.
+    pub fn write_buffer_size(&mut self, bytes: usize) -> &mut Self {
+        self.write_buffer_size = Some(bytes);
+        self
+    }
+
+    /// Maximum number of open files. `-1` keeps all files open.
+    pub fn max_open_files(&mut self, files: i32) -> &mut Self {
+        self.max_open_files = Some(files);
+        self
+    }
+
+    /// Syncs the write-ahead log before every write returns. Defaults to false.
+    pub fn sync_writes(&mut self, sync: bool) -> &mut Self {
+        self.sync_writes = sync;
+        self
+    }
+
+    /// Uses `fsync` instead of `fdatasync` when syncing files. Defaults to false.
+    pub fn use_fsync(&mut self, fsync: bool) -> &mut Self {
+        self.use_fsync = fsync;
+        self
+    }
+
+    /// Number of bits per key used for bloom filters on keys and index prefixes,
+    /// which speed up lookups and scans of missing entries. Defaults to 10.
+    pub fn bloom_filter(&mut self, bits_per_key: f64) -> &mut Self {
+        self.bloom_filter_bits = Some(bits_per_key);
+        self
+    }
+
+    /// Disables bloom filters.
+    pub fn no_bloom_filter(&mut self) -> &mut Self {
+        self.bloom_filter_bits = None;
+        self
+    }
+
+    /// Stores every table without a `column_family` attribute in its own
+    /// column family named `table_<TABLE>`. Defaults to false.
+    ///
+    /// Values stored before enabling this are not moved and become invisible.
+    pub fn column_family_per_table(&mut self, enabled: bool) -> &mut Self {
+        self.column_family_per_table = enabled;
+        self
+    }
+
+    /// Compression used for the column family with the given name,
+    /// overriding [OpenOptions::compression].
+    pub fn column_family_compression(
+        &mut self,
+        name: impl Into<String>,
+        compression: Compression,
+    ) -> &mut Self {
+        self.compression_per_family.insert(name.into(), compression);
+        self
+    }
+
+    /// Fails opening with [Error::SchemaMismatch] if a registered table
+    /// differs from its catalog entry. Defaults to false.
+    ///
+    /// The catalog is compared on every open, without the check the differences
+    /// are returned by [Database::schema_changes]. See [Database::schema_diff].
+    pub fn check_schema(&mut self, check: bool) -> &mut Self {
+        self.check_schema = check;
+        self
+    }
+
+    /// Fails opening with [Error::Collision] if two registered types claim the number
+    /// of a table or single stored in the database without declaring different
+    /// versions, see [Registration](crate::Registration). Defaults to true.
+    ///
+    /// Tables are only considered once they are recorded in the catalog.
+    /// Disable the check if the types of several databases linked into one binary
+    /// share numbers.
+    pub fn check_collisions(&mut self, check: bool) -> &mut Self {
+        self.check_collisions = check;
+        self
+    }
+
+    /// Rebuilds the indices of every registered table whose indices differ
+    /// from its catalog entry when opening, see [Table::rebuild_indexes]. Defaults to false.
+    ///
+    /// Only applies to [OpenOptions::open], tables are checked before [OpenOptions::check_schema].
+    pub fn rebuild_indexes(&mut self, rebuild: bool) -> &mut Self {
+        self.rebuild_indexes = rebuild;
+        self
+    }
+
+    /// Records the writes of [Table::create], [Table::update], [Table::delete],
+    /// [Single::put](crate::Single::put) and [Single::delete](crate::Single::delete)
+    /// in the change feed, see [Database::changes_since]. Defaults to false.
+    ///
+    /// Other writes are not recorded:
+    /// - [Table::upgrade_all] and [Table::move_to]
+    /// - [Table::truncate] and [Table::drop]
+    /// - imports, [migrate_table](crate::migrate_table), [Table::repair] and the rebuilds of indices
+    /// - writes through the underlying [DB]
+    pub fn change_feed(&mut self, enabled: bool) -> &mut Self {
+        self.change_feed = enabled;
+        self
+    }
+
+    /// Keeps at least the given number of the latest events in the change feed,
+    /// older ones are pruned periodically. Defaults to keeping all events.
+    pub fn change_retention(&mut self, events: u64) -> &mut Self {
+        self.change_retention = Some(events.max(1));
+        self
+    }
+
+    /// Opens the database at the given path with these options.
+    ///
+    /// All existing column families are opened, missing ones
+    /// are created when a table stored in them is first written.
+    pub fn open<P: AsRef<Path>>(&self, path: P) -> Result<Database> {
+        let path = path.as_ref();
+        if self.create_if_missing {
+            std::fs::create_dir_all(path).map_err(Error::CreateDirectory)?;
+        }
+
+        let options = self.options()?;
+        let families = self.families(&options, path);
+        let db = DB::open_cf_descriptors(&options, path, families)?;
+        let db = self.database(db, options)?;
+        if self.rebuild_indexes {
+            db.rebuild_changed_indexes()?;
+        }
+        self.check(db)
+    }
+
+    /// Opens the database at the given path without taking its lock.
+    /// Changes written by other instances afterwards are not visible.
+    pub fn open_read_only<P: AsRef<Path>>(&self, path: P) -> Result<Database<ReadOnly>> {
+        let options = self.options()?;
+        let families = self.families(&options, path.as_ref());
+        let db = DB::open_cf_descriptors_read_only(&options, path, families, false)?;
+        self.check(self.database(db, options)?)
+    }
+
+    /// Opens the database at `primary` as secondary instance keeping its own logs in `secondary`.
+    /// Changes of the primary become visible after [Database::try_catch_up_with_primary].
+    pub fn open_as_secondary<P: AsRef<Path>, Q: AsRef<Path>>(
+        &self,
+        primary: P,
+        secondary: Q,
+    ) -> Result<Database<Secondary>> {
+        let mut options = self.options()?;
+        // secondary instances have to keep all files open
+        options.set_max_open_files(-1);
+        let families = self.families(&options, primary.as_ref());
+        let db = DB::open_cf_descriptors_as_secondary(
+            &options,
+            primary.as_ref(),
+            secondary.as_ref(),
+            families,
+        )?;
+        self.check(self.database(db, options)?)
+    }
+
+    /// See [Database::column_family].
+    pub(crate) fn column_family<T: Table>(&self) -> Cow<'static, str> {
+        self.column_family_of::<T>(T::TABLE)
+    }
+
+    /// Column family of the given table if it was stored under another number.
+    pub(crate) fn column_family_of<T: Table>(&self, table: u32) -> Cow<'static, str> {
+        match (T::COLUMN_FAMILY, self.column_family_per_table) {
+            (Some(name), _) => Cow::Borrowed(name),
+            (None, true) => Cow::Owned(format!("table_{table}")),
+            (None, false) => Cow::Borrowed(DEFAULT_COLUMN_FAMILY_NAME),
+        }
+    }
+
+    /// Returns descriptors for all column families of the database at the given path.
+    fn families(&self, options: &Options, path: &Path) -> Vec<ColumnFamilyDescriptor> {
+        // listing fails if the database does not exist yet
+        let mut names = DB::list_cf(options, path).unwrap_or_default();
+        if !names.iter().any(|name| name == DEFAULT_COLUMN_FAMILY_NAME) {
+            names.push(DEFAULT_COLUMN_FAMILY_NAME.to_string());
+        }
+
+        names
+            .iter()
+            .map(|name| ColumnFamilyDescriptor::new(name, self.family_options(options, name)))
+            .collect()
+    }
+
+    fn database<M: Mode>(&self, db: DB, options: Options) -> Result<Database<M>> {
+        let mut write_options = WriteOptions::default();
+        write_options.set_sync(self.sync_writes);
+
+        let db = Database {
+            db,
+            options,
+            write_options,
+            config: self.clone(),
+            recorded: RwLock::default(),
+            schema_changes: Vec::new(),
+            changes: ChangeLog::new(self.change_feed, self.change_retention),
+            _mode: PhantomData,
+        };
+
+        if self.check_collisions {
+            registry::check(&db)?;
+        }
+        Ok(db)
+    }
+
+    /// Compares the catalog with the registered tables,
+    /// failing with [Error::SchemaMismatch] if enabled and a table changed.
+    fn check<M: Mode>(&self, mut db: Database<M>) -> Result<Database<M>> {
+        db.schema_changes = db.schema_diff()?;
+        if self.check_schema && !db.schema_changes.is_empty() {
+            return Err(Error::SchemaMismatch {
+                diffs: db.schema_changes,
+            });
+        }
+        Ok(db)
+    }
+
+    fn family_options(&self, options: &Options, name: &str) -> Options {
+        let mut options = options.clone();
+        if let Some(compression) = self.compression_per_family.get(name) {
+            options.set_compression_type((*compression).into());
+        }
+        options
+    }
+
+    fn options(&self) -> Result<Options> {
+        let mut options = Options::default();
+        options.create_if_missing(self.create_if_missing);
+        options.set_use_fsync(self.use_fsync);
+        // all keys of an index share the table and index prefix
+        options.set_prefix_extractor(SliceTransform::create_fixed_prefix(PREFIX_LEN));
+
+        if let Some(compression) = self.compression {
+            options.set_compression_type(compression.into());
+        }
+        if let Some(bytes) = self.write_buffer_size {
+            options.set_write_buffer_size(bytes);
+        }
+        if let Some(files) = self.max_open_files {
+            options.set_max_open_files(files);
+        }
+
+        let mut table = BlockBasedOptions::default();
+        if let Some(bytes) = self.cache_size {
+            table.set_block_cache(&Cache::new_lru_cache(bytes)?);
+        }
+        if let Some(bits) = self.bloom_filter_bits {
+            table.set_bloom_filter(bits, false);
+            table.set_whole_key_filtering(true);
+            options.set_memtable_prefix_bloom_ratio(0.1);
+        }
+        options.set_block_based_table_factory(&table);
+
+        Ok(options)
+    }
+}
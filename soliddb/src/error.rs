@@ -1,30 +1,255 @@
+use ulid::Ulid;
+
+use crate::{Claim, SchemaDiff};
+
 /// Result type for the soliddb crate.
 pub type Result<T> = std::result::Result<T, Error>;
 
 /// The Error type for the soliddb crate.
+///
+/// Use [Error::kind] to match on the category of an error
+/// without depending on the context carried by the variants.
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     /// Returned if the given key was not found.
-    #[error("not found")]
-    NotFound,
+    /// The id is missing for lookups by unique value and singles.
+    #[error("{table}{} not found", .id.map(|id| format!(" {id}")).unwrap_or_default())]
+    NotFound {
+        /// Name of the table or single.
+        table: &'static str,
+        /// Id of the requested entry.
+        id: Option<Ulid>,
+    },
+
+    /// Returned if values for some of the requested ids were not found.
+    #[error("{table} {ids:?} not found")]
+    MissingIds {
+        /// Name of the table.
+        table: &'static str,
+        /// Ids without a value, in the order they were requested.
+        ids: Vec<Ulid>,
+    },
 
     /// Returned if the given value would violate unique constraints.
-    #[error("already exists")]
-    AlreadyExists,
+    #[error("{table}.{} already exists", .index_name.unwrap_or("?"))]
+    AlreadyExists {
+        /// Name of the table.
+        table: &'static str,
+        /// Number of the violated unique index.
+        index: u8,
+        /// Name of the field the violated index was derived from.
+        index_name: Option<&'static str>,
+        /// Id of the entry already holding the value.
+        id: Ulid,
+    },
+
+    /// Returned if encoding a value failed.
+    #[error("encoding {table} failed: {source}")]
+    Encoding {
+        /// Name of the table or single.
+        table: &'static str,
+        /// The underlying error.
+        source: ron::Error,
+    },
+
+    /// Returned if decoding a stored value failed.
+    #[error("decoding {table} from {len} bytes failed: {source}")]
+    Decoding {
+        /// Name of the table or single.
+        table: &'static str,
+        /// Length of the stored value.
+        len: usize,
+        /// The underlying error including its position.
+        source: ron::error::SpannedError,
+    },
 
-    /// Returned if encoding or decoding failed.
-    #[error("encoding failed")]
-    Encoding(#[from] ron::Error),
+    /// Returned if the given index does not exist on the table.
+    #[error("{table} has no index {index}")]
+    UnknownIndex {
+        /// Name of the table.
+        table: &'static str,
+        /// Number of the requested index.
+        index: u8,
+    },
 
     /// Returned if the found key has the wrong format.
-    #[error("malformed key")]
-    MalformedKey,
+    #[error("malformed key {bytes:02x?}")]
+    MalformedKey {
+        /// The raw key or id bytes.
+        bytes: Vec<u8>,
+    },
 
     /// Returned if rocksdb returned an error.
     #[error("internal rocksdb error: {0}")]
     Internal(#[from] rocksdb::Error),
 
+    /// Returned if rocksdb behaved unexpectedly without returning an error.
+    #[error("unexpected rocksdb behavior: {reason}")]
+    Unexpected {
+        /// What happened.
+        reason: String,
+    },
+
     /// Returned if the given directory could not be created.
     #[error("database creation failed: {0}")]
     CreateDirectory(std::io::Error),
+
+    /// Returned when opening a database if two types claim a stored table or single number
+    /// without declaring different versions, see [OpenOptions::check_collisions](crate::OpenOptions::check_collisions).
+    #[error("{claim} {id} is claimed by both {first} and {second}")]
+    Collision {
+        /// Whether a table or single number is claimed twice.
+        claim: Claim,
+        /// The claimed number.
+        id: u32,
+        /// Path of the first type.
+        first: &'static str,
+        /// Path of the second type.
+        second: &'static str,
+    },
+
+    /// Returned if the schema version of the database is newer
+    /// than the latest version known to the [Migrator](crate::Migrator).
+    #[error("schema version {version} is newer than the latest known version {latest}")]
+    UnknownVersion {
+        /// Version stored in the database.
+        version: u32,
+        /// Number of steps of the migrator.
+        latest: u32,
+    },
+
+    /// Returned when opening a database with [OpenOptions::check_schema](crate::OpenOptions::check_schema)
+    /// if registered tables differ from their catalog entries.
+    #[error("schema of {} changed", .diffs.iter().map(|diff| diff.name.as_str()).collect::<Vec<_>>().join(", "))]
+    SchemaMismatch {
+        /// Differences of all changed tables.
+        diffs: Vec<SchemaDiff>,
+    },
+
+    /// Returned if a table can not be moved to the given number
+    /// because another table is stored there.
+    #[error("table {table} is already in use")]
+    TableInUse {
+        /// Number of the target table.
+        table: u32,
+    },
+
+    /// Returned by [Hooks](crate::Hooks) to cancel a write.
+    #[error("{table} rejected: {reason}")]
+    Rejected {
+        /// Name of the table.
+        table: &'static str,
+        /// Why the write was rejected.
+        reason: String,
+    },
+
+    /// Returned if reading or writing an export failed.
+    #[error("io error: {0}")]
+    Io(std::io::Error),
+
+    /// Returned if a record of an export could not be encoded or decoded.
+    #[error("{table} record on line {line} is invalid: {source}")]
+    InvalidRecord {
+        /// Name of the table or single.
+        table: &'static str,
+        /// Line of the record, starting at 1.
+        line: usize,
+        /// The underlying error.
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    /// Returned if a dump contains a record of a table or single
+    /// which was not added to the [Dump](crate::Dump).
+    #[error("unknown record on line {line}")]
+    UnknownRecord {
+        /// Line of the record, starting at 1.
+        line: usize,
+        /// Number of the table of the record.
+        table: Option<u32>,
+        /// Number of the single of the record.
+        single: Option<u32>,
+    },
+}
+
+/// Stable category of an [Error].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// See [Error::NotFound] and [Error::MissingIds].
+    NotFound,
+    /// See [Error::AlreadyExists].
+    AlreadyExists,
+    /// See [Error::Encoding].
+    Encoding,
+    /// See [Error::Decoding].
+    Decoding,
+    /// See [Error::UnknownIndex].
+    UnknownIndex,
+    /// See [Error::MalformedKey].
+    MalformedKey,
+    /// See [Error::Internal] and [Error::Unexpected].
+    Internal,
+    /// See [Error::CreateDirectory].
+    CreateDirectory,
+    /// See [Error::Collision].
+    Collision,
+    /// See [Error::UnknownVersion].
+    UnknownVersion,
+    /// See [Error::SchemaMismatch].
+    SchemaMismatch,
+    /// See [Error::TableInUse].
+    TableInUse,
+    /// See [Error::Rejected].
+    Rejected,
+    /// See [Error::Io].
+    Io,
+    /// See [Error::InvalidRecord] and [Error::UnknownRecord].
+    InvalidRecord,
+}
+
+impl Error {
+    /// Returns the category of this error.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Error::NotFound { .. } | Error::MissingIds { .. } => ErrorKind::NotFound,
+            Error::AlreadyExists { .. } => ErrorKind::AlreadyExists,
+            Error::Encoding { .. } => ErrorKind::Encoding,
+            Error::Decoding { .. } => ErrorKind::Decoding,
+            Error::UnknownIndex { .. } => ErrorKind::UnknownIndex,
+            Error::MalformedKey { .. } => ErrorKind::MalformedKey,
+            Error::Internal(_) | Error::Unexpected { .. } => ErrorKind::Internal,
+            Error::CreateDirectory(_) => ErrorKind::CreateDirectory,
+            Error::Collision { .. } => ErrorKind::Collision,
+            Error::UnknownVersion { .. } => ErrorKind::UnknownVersion,
+            Error::SchemaMismatch { .. } => ErrorKind::SchemaMismatch,
+            Error::TableInUse { .. } => ErrorKind::TableInUse,
+            Error::Rejected { .. } => ErrorKind::Rejected,
+            Error::Io(_) => ErrorKind::Io,
+            Error::InvalidRecord { .. } | Error::UnknownRecord { .. } => ErrorKind::InvalidRecord,
+        }
+    }
+
+    /// Returns the name of the table or single involved, if known.
+    pub fn table(&self) -> Option<&'static str> {
+        match self {
+            Error::NotFound { table, .. }
+            | Error::MissingIds { table, .. }
+            | Error::AlreadyExists { table, .. }
+            | Error::Encoding { table, .. }
+            | Error::Decoding { table, .. }
+            | Error::UnknownIndex { table, .. }
+            | Error::Rejected { table, .. }
+            | Error::InvalidRecord { table, .. } => Some(table),
+            Error::MalformedKey { .. }
+            | Error::Internal(_)
+            | Error::Unexpected { .. }
+            | Error::CreateDirectory(_)
+            | Error::Collision { .. }
+            | Error::UnknownVersion { .. }
+            | Error::SchemaMismatch { .. }
+            | Error::TableInUse { .. }
+            | Error::Io(_)
+            | Error::UnknownRecord { .. } => None,
+        }
+    }
 }
@@ -0,0 +1,538 @@
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, Write};
+
+use rocksdb::{WriteBatch, DEFAULT_COLUMN_FAMILY_NAME};
+use serde::de::{DeserializeOwned, IgnoredAny};
+use serde::{Deserialize, Serialize};
+use ulid::Ulid;
+
+use crate::database::Family;
+use crate::keys::{id_from_slice, primary_key, single_key, unique_key};
+use crate::table::{to_bytes, REBUILD_BATCH_SIZE};
+use crate::{
+    registrations, Database, Error, Mode, ReadOnly, ReadWrite, Result, Secondary, Single, Table,
+    WithId,
+};
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Text format of exported records, one record per line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Format {
+    /// Every line is a JSON document. Requires the `serde_json` feature.
+    #[cfg(feature = "serde_json")]
+    JsonLines,
+    /// Every line is a RON document, the encoding used for stored values.
+    Ron,
+}
+
+impl Format {
+    fn write<T: Serialize>(
+        self,
+        writer: &mut dyn Write,
+        table: &'static str,
+        line: usize,
+        value: &T,
+    ) -> Result<()> {
+        let encoded: std::result::Result<String, BoxError> = match self {
+            #[cfg(feature = "serde_json")]
+            Format::JsonLines => serde_json::to_string(value).map_err(Into::into),
+            Format::Ron => ron::to_string(value).map_err(Into::into),
+        };
+        let encoded = encoded.map_err(|source| Error::InvalidRecord {
+            table,
+            line,
+            source,
+        })?;
+        writeln!(writer, "{encoded}").map_err(Error::Io)
+    }
+
+    fn read<T: DeserializeOwned>(self, table: &'static str, line: usize, text: &str) -> Result<T> {
+        let decoded: std::result::Result<T, BoxError> = match self {
+            #[cfg(feature = "serde_json")]
+            Format::JsonLines => serde_json::from_str(text).map_err(Into::into),
+            Format::Ron => ron::from_str(text).map_err(Into::into),
+        };
+        decoded.map_err(|source| Error::InvalidRecord {
+            table,
+            line,
+            source,
+        })
+    }
+}
+
+/// Iterates over the non-empty lines of the reader with their line numbers.
+fn lines(reader: impl BufRead) -> impl Iterator<Item = Result<(usize, String)>> {
+    reader
+        .lines()
+        .enumerate()
+        .map(|(index, line)| line.map(|line| (index + 1, line)).map_err(Error::Io))
+        .filter(|line| !matches!(line, Ok((_, text)) if text.trim().is_empty()))
+}
+
+pub(crate) fn export<T: Table, M: Mode>(
+    db: &Database<M>,
+    mut writer: impl Write,
+    format: Format,
+) -> Result<usize> {
+    let mut count = 0;
+    for item in T::iter(db) {
+        count += 1;
+        format.write(&mut writer, T::NAME, count, &Row(item?))?;
+    }
+
+    Ok(count)
+}
+
+pub(crate) fn import<T: Table>(
+    db: &Database,
+    reader: impl BufRead,
+    format: Format,
+) -> Result<usize> {
+    let mut staged = Staged::table::<T>();
+    for line in lines(reader) {
+        let (line, text) = line?;
+        let Row::<T>(row) = format.read(T::NAME, line, &text)?;
+        staged.add_row(row)?;
+    }
+
+    store(db, &[staged])
+}
+
+/// Checks all staged records before writing any of them,
+/// returning the number of written records.
+fn store(db: &Database, staged: &[Staged]) -> Result<usize> {
+    for records in staged {
+        (records.check)(db, &records.unique)?;
+    }
+
+    let mut batch = Batch::new(db);
+    for records in staged {
+        let family = (records.family)(db)?;
+        for (key, value) in &records.records {
+            batch.batch.put_cf(&family, key, value);
+            batch.written()?;
+        }
+    }
+    let count = batch.finish()?;
+
+    for records in staged {
+        if !records.records.is_empty() {
+            (records.finish)(db)?;
+        }
+    }
+    Ok(count)
+}
+
+/// Records of a table or single read from an import, encoded for storing.
+struct Staged {
+    /// Keys with the encoded values.
+    records: Vec<(Vec<u8>, Vec<u8>)>,
+    /// Unique index entries of the imported rows.
+    unique: Vec<UniqueEntry>,
+    /// Checks the unique index entries, see [check_unique].
+    check: fn(&Database, &[UniqueEntry]) -> Result<()>,
+    /// Returns the column family the records are written to.
+    family: fn(&Database) -> Result<Family<'_>>,
+    /// Called once all records are written.
+    finish: fn(&Database) -> Result<()>,
+}
+
+impl Staged {
+    /// Rows of the given table, whose indices are rebuilt once they are written.
+    fn table<T: Table>() -> Self {
+        Self {
+            records: Vec::new(),
+            unique: Vec::new(),
+            check: check_unique::<T>,
+            family: Database::write_family::<T>,
+            finish: T::rebuild_indexes,
+        }
+    }
+
+    /// Values of a single.
+    fn single() -> Self {
+        Self {
+            records: Vec::new(),
+            unique: Vec::new(),
+            check: |_, _| Ok(()),
+            family: default_family,
+            finish: |_| Ok(()),
+        }
+    }
+
+    fn add_row<T: Table>(&mut self, row: WithId<T>) -> Result<()> {
+        let WithId { id, value } = row;
+        for index in T::UNIQUE_INDICES {
+            let key = unique_key(T::TABLE, *index, &value.unique_value(*index)?);
+            let index = *index;
+            self.unique.push(UniqueEntry { index, key, id });
+        }
+
+        let value = to_bytes(T::NAME, &value)?;
+        self.records.push((primary_key(T::TABLE, id), value));
+        Ok(())
+    }
+
+    fn add_value<S: Single>(&mut self, value: &S) -> Result<()> {
+        let value = to_bytes(S::NAME, value)?;
+        self.records.push((single_key(S::SINGLE), value));
+        Ok(())
+    }
+}
+
+/// Key of a unique index entry of an imported row.
+struct UniqueEntry {
+    index: u8,
+    key: Vec<u8>,
+    id: Ulid,
+}
+
+/// Fails with [Error::AlreadyExists] if two imported rows share a unique value,
+/// or if a unique value is held by a stored row which is not imported.
+fn check_unique<T: Table>(db: &Database, entries: &[UniqueEntry]) -> Result<()> {
+    let family = db.family::<T>();
+    let ids: HashSet<_> = entries.iter().map(|entry| entry.id).collect();
+    let mut imported = HashMap::new();
+
+    for entry in entries {
+        let holder = match imported.insert(&entry.key, entry.id) {
+            Some(other) if other != entry.id => Some(other),
+            _ => match &family {
+                Some(family) => match db.get_pinned_cf(family, &entry.key)? {
+                    Some(stored) => Some(id_from_slice(&stored)?).filter(|id| !ids.contains(id)),
+                    None => None,
+                },
+                None => None,
+            },
+        };
+
+        if let Some(id) = holder {
+            return Err(Error::AlreadyExists {
+                table: T::NAME,
+                index: entry.index,
+                index_name: T::index_name(entry.index),
+                id,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+fn default_family(db: &Database) -> Result<Family<'_>> {
+    db.create_family(DEFAULT_COLUMN_FAMILY_NAME)
+}
+
+/// Writes imported values in batches of [REBUILD_BATCH_SIZE].
+struct Batch<'a> {
+    db: &'a Database,
+    batch: WriteBatch,
+    count: usize,
+}
+
+impl<'a> Batch<'a> {
+    fn new(db: &'a Database) -> Self {
+        Self {
+            db,
+            batch: WriteBatch::default(),
+            count: 0,
+        }
+    }
+
+    fn written(&mut self) -> Result<()> {
+        self.count += 1;
+        if self.count % REBUILD_BATCH_SIZE == 0 {
+            self.db.write(std::mem::take(&mut self.batch))?;
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<usize> {
+        self.db.write(self.batch)?;
+        Ok(self.count)
+    }
+}
+
+/// Encodes a [WithId] without flattening the value, which RON does not support.
+#[derive(Serialize, Deserialize)]
+#[serde(transparent)]
+#[serde(bound(serialize = "T: Serialize", deserialize = "T: Deserialize<'de>"))]
+struct Row<T>(#[serde(with = "RowDef")] WithId<T>);
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "WithId")]
+struct RowDef<T> {
+    id: Ulid,
+    value: T,
+}
+
+/// Line of a dump, holding a row of a table or the value of a single.
+#[derive(Serialize, Deserialize)]
+struct Entry<V> {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    table: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    single: Option<u32>,
+    value: V,
+}
+
+type ExportFn<M> = fn(&Database<M>, &mut dyn Write, Format, &mut usize) -> Result<()>;
+type ImportFn = fn(&mut Staged, Format, usize, &str) -> Result<()>;
+
+struct Registered<M: Mode> {
+    table: Option<u32>,
+    single: Option<u32>,
+    export: ExportFn<M>,
+    stage: fn() -> Staged,
+    import: ImportFn,
+}
+
+/// Adds a registered table or single to a [Dump] of each [Mode].
+/// Created by the derive macros, see [Dump::all].
+#[derive(Debug, Clone, Copy)]
+pub struct DumpFns {
+    pub(crate) read_write: fn(Dump<ReadWrite>) -> Dump<ReadWrite>,
+    pub(crate) read_only: fn(Dump<ReadOnly>) -> Dump<ReadOnly>,
+    pub(crate) secondary: fn(Dump<Secondary>) -> Dump<Secondary>,
+}
+
+impl DumpFns {
+    /// Adds all rows of the given table.
+    pub const fn table<T: Table>() -> Self {
+        Self {
+            read_write: Dump::table::<T>,
+            read_only: Dump::table::<T>,
+            secondary: Dump::table::<T>,
+        }
+    }
+
+    /// Adds the value of the given single.
+    pub const fn single<S: Single>() -> Self {
+        Self {
+            read_write: Dump::single::<S>,
+            read_only: Dump::single::<S>,
+            secondary: Dump::single::<S>,
+        }
+    }
+}
+
+/// Export and import of several tables and singles as one dump.
+///
+/// Every line holds the table or single number and the value.
+/// The mode is the one of the database exported from.
+///
+/// ```no_run
+/// # use serde::{Deserialize, Serialize};
+/// # use soliddb::{Dump, Format, Single, Table};
+/// # #[derive(Serialize, Deserialize, Table)]
+/// # #[solid(table = 1)]
+/// # struct User {}
+/// # #[derive(Serialize, Deserialize, Single)]
+/// # #[solid(single = 1)]
+/// # struct Config {}
+/// # fn main() -> soliddb::Result<()> {
+/// let db = soliddb::open_read_only("/tmp/soliddb")?;
+/// let file = std::fs::File::create("/tmp/dump.ron").map_err(soliddb::Error::Io)?;
+/// Dump::new()
+///     .table::<User>()
+///     .single::<Config>()
+///     .export(&db, file, Format::Ron)?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Dump<M: Mode = ReadWrite> {
+    entries: Vec<Registered<M>>,
+}
+
+impl<M: Mode> Default for Dump<M> {
+    fn default() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+}
+
+impl<M: Mode> Dump<M> {
+    /// Creates a dump without any tables or singles.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a dump of all tables and singles deriving [Table] or [Single],
+    /// see [registrations].
+    ///
+    /// Numbers claimed by several versions are added with their latest version,
+    /// numbers claimed by several unrelated types with the first one registered.
+    pub fn all() -> Self {
+        let mut registrations = registrations();
+        registrations.sort_by_key(|registration| {
+            let version = registration.version.map(std::cmp::Reverse);
+            (
+                registration.claim,
+                registration.id,
+                version.is_none(),
+                version,
+            )
+        });
+        registrations.dedup_by_key(|registration| (registration.claim, registration.id));
+
+        registrations
+            .into_iter()
+            .fold(Self::new(), |dump, registration| {
+                M::add_to_dump(&registration.dump, dump)
+            })
+    }
+
+    /// Adds all rows of the given table.
+    pub fn table<T: Table>(mut self) -> Self {
+        self.entries.push(Registered {
+            table: Some(T::TABLE),
+            single: None,
+            export: export_table::<T, M>,
+            stage: Staged::table::<T>,
+            import: import_table::<T>,
+        });
+        self
+    }
+
+    /// Adds the value of the given single, if it is stored.
+    pub fn single<S: Single>(mut self) -> Self {
+        self.entries.push(Registered {
+            table: None,
+            single: Some(S::SINGLE),
+            export: export_single::<S, M>,
+            stage: Staged::single,
+            import: import_single::<S>,
+        });
+        self
+    }
+
+    /// Writes all added tables and singles, returning the number of records.
+    pub fn export(
+        &self,
+        db: &Database<M>,
+        mut writer: impl Write,
+        format: Format,
+    ) -> Result<usize> {
+        let mut count = 0;
+        for entry in &self.entries {
+            (entry.export)(db, &mut writer, format, &mut count)?;
+        }
+
+        Ok(count)
+    }
+}
+
+impl Dump {
+    /// Restores all records of a dump with their original ids,
+    /// returning the number of records.
+    ///
+    /// Fails on records of tables or singles which were not added.
+    /// All records are read and checked for duplicate unique values
+    /// before the first one is written, see [Table::import].
+    pub fn import(&self, db: &Database, reader: impl BufRead, format: Format) -> Result<usize> {
+        let mut staged: Vec<_> = self.entries.iter().map(|entry| (entry.stage)()).collect();
+
+        for line in lines(reader) {
+            let (line, text) = line?;
+            let header: Entry<IgnoredAny> = format.read("dump", line, &text)?;
+            let position = self
+                .entries
+                .iter()
+                .position(|entry| match header.table {
+                    Some(table) => entry.table == Some(table),
+                    None => header.single.is_some() && entry.single == header.single,
+                })
+                .ok_or(Error::UnknownRecord {
+                    line,
+                    table: header.table,
+                    single: header.single,
+                })?;
+
+            (self.entries[position].import)(&mut staged[position], format, line, &text)?;
+        }
+
+        store(db, &staged)
+    }
+}
+
+fn export_table<T: Table, M: Mode>(
+    db: &Database<M>,
+    writer: &mut dyn Write,
+    format: Format,
+    count: &mut usize,
+) -> Result<()> {
+    for item in T::iter(db) {
+        *count += 1;
+        let entry = Entry {
+            table: Some(T::TABLE),
+            single: None,
+            value: Row(item?),
+        };
+        format.write(writer, T::NAME, *count, &entry)?;
+    }
+
+    Ok(())
+}
+
+fn import_table<T: Table>(
+    staged: &mut Staged,
+    format: Format,
+    line: usize,
+    text: &str,
+) -> Result<()> {
+    let entry: Entry<Row<T>> = format.read(T::NAME, line, text)?;
+    staged.add_row(entry.value.0)
+}
+
+fn export_single<S: Single, M: Mode>(
+    db: &Database<M>,
+    writer: &mut dyn Write,
+    format: Format,
+    count: &mut usize,
+) -> Result<()> {
+    let value = match S::get(db) {
+        Ok(value) => value,
+        Err(Error::NotFound { .. }) => return Ok(()),
+        Err(err) => return Err(err),
+    };
+
+    *count += 1;
+    let entry = Entry {
+        table: None,
+        single: Some(S::SINGLE),
+        value,
+    };
+    format.write(writer, S::NAME, *count, &entry)
+}
+
+fn import_single<S: Single>(
+    staged: &mut Staged,
+    format: Format,
+    line: usize,
+    text: &str,
+) -> Result<()> {
+    let entry: Entry<S> = format.read(S::NAME, line, text)?;
+    staged.add_value(&entry.value)
+}
+
+impl<M: Mode> std::fmt::Debug for Dump<M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let tables: Vec<_> = self
+            .entries
+            .iter()
+            .filter_map(|entry| entry.table)
+            .collect();
+        let singles: Vec<_> = self
+            .entries
+            .iter()
+            .filter_map(|entry| entry.single)
+            .collect();
+        f.debug_struct("Dump")
+            .field("tables", &tables)
+            .field("singles", &singles)
+            .finish()
+    }
+}
@@ -0,0 +1,87 @@
+use rocksdb::WriteBatch;
+use ulid::Ulid;
+
+use crate::{Database, Result, Table, WithId};
+
+/// Callbacks of [Table::create], [Table::update] and [Table::delete],
+/// all of which do nothing by default.
+///
+/// Called for derived tables with the `hooks` attribute, which requires
+/// [Clone] since the `before_*` hooks change a copy of the written value.
+/// The `before_*` hooks are called before the batch of the write is assembled,
+/// writes they add to [WriteContext::batch] are applied atomically with it.
+/// Returning an error, usually [Error::Rejected](crate::Error::Rejected),
+/// from any of them cancels the write.
+/// The `after_*` hooks are called once the write succeeded.
+///
+/// ```no_run
+/// # use serde::{Deserialize, Serialize};
+/// # use soliddb::{Error, Hooks, Result, Table, WithId, WriteContext};
+/// #[derive(Clone, Serialize, Deserialize, Table)]
+/// #[solid(table = 1, hooks)]
+/// struct Post {
+///     title: String,
+///     updated_at: u64,
+/// }
+///
+/// impl Hooks for Post {
+///     fn before_update(&mut self, _: &WithId<Self>, _: &mut WriteContext<'_>) -> Result<()> {
+///         self.updated_at += 1;
+///         Ok(())
+///     }
+///
+///     fn before_delete(item: &WithId<Self>, _: &mut WriteContext<'_>) -> Result<()> {
+///         Err(Error::Rejected {
+///             table: Self::NAME,
+///             reason: format!("{} is archived", item.value.title),
+///         })
+///     }
+/// }
+/// ```
+pub trait Hooks: Table + Clone {
+    /// Called with the value to create, which may be changed before it is stored.
+    fn before_create(&mut self, ctx: &mut WriteContext<'_>) -> Result<()> {
+        let _ = ctx;
+        Ok(())
+    }
+
+    /// Called on the created value with its new id.
+    fn after_create(&self, id: Ulid) {
+        let _ = id;
+    }
+
+    /// Called with the new value, which may be changed before it is stored,
+    /// and the stored one.
+    fn before_update(&mut self, old: &WithId<Self>, ctx: &mut WriteContext<'_>) -> Result<()> {
+        let _ = (old, ctx);
+        Ok(())
+    }
+
+    /// Called on the value which replaced the old one.
+    fn after_update(&self, old: &WithId<Self>) {
+        let _ = old;
+    }
+
+    /// Called with the value to delete.
+    fn before_delete(item: &WithId<Self>, ctx: &mut WriteContext<'_>) -> Result<()> {
+        let _ = (item, ctx);
+        Ok(())
+    }
+
+    /// Called with the deleted value.
+    fn after_delete(item: &WithId<Self>) {
+        let _ = item;
+    }
+}
+
+/// Write passed to the `before_*` [Hooks].
+pub struct WriteContext<'a> {
+    /// Database the value is written to.
+    pub db: &'a Database,
+
+    /// Id of the written value.
+    pub id: Ulid,
+
+    /// Batch of the write, applied once the value and its index entries are added.
+    pub batch: &'a mut WriteBatch,
+}
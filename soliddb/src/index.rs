@@ -1,10 +1,53 @@
-use std::{borrow::Cow, collections::BTreeSet};
+use std::{
+    borrow::Cow,
+    collections::BTreeSet,
+    num::{
+        NonZeroI128, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8, NonZeroIsize, NonZeroU128,
+        NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU8, NonZeroUsize,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
 /// The IndexValue trait has to be implemented for types
 /// that are used as either unique or indexed field.
+///
+/// Floats, durations and timestamps are encoded so that their byte
+/// representations sort in the same order as the values themselves.
+/// Byte slices and vectors are stored as raw bytes.
+///
+/// Elements of tuples, arrays and slices are encoded with [IndexValue::append_element],
+/// so `("ab", "c")` and `("a", "bc")` are stored as different values.
+/// Vectors and sets keep concatenating the plain representation of their elements
+/// as stored by earlier versions, so `vec!["ab"]` and `vec!["a", "b"]` share one value.
+///
+/// Can be derived for fieldless enums and newtype structs.
 pub trait IndexValue {
     /// Returns a byte representiation of the given type.
     fn as_bytes(&self) -> Vec<u8>;
+
+    /// Appends the representation used as element of a tuple, array or slice.
+    ///
+    /// Escapes `0x00` as `0x00 0xFF` and terminates the value with `0x00 0x01`,
+    /// which keeps the order of the values. Types of a fixed length
+    /// append [IndexValue::as_bytes] unchanged.
+    fn append_element(&self, bytes: &mut Vec<u8>) {
+        for byte in self.as_bytes() {
+            bytes.push(byte);
+            if byte == 0 {
+                bytes.push(u8::MAX);
+            }
+        }
+        bytes.extend_from_slice(&[0, 1]);
+    }
+}
+
+/// Implements [IndexValue::append_element] for types of a fixed length.
+macro_rules! fixed_length {
+    () => {
+        fn append_element(&self, bytes: &mut Vec<u8>) {
+            bytes.extend(self.as_bytes());
+        }
+    };
 }
 
 impl IndexValue for String {
@@ -13,12 +56,22 @@ impl IndexValue for String {
     }
 }
 
-impl IndexValue for &str {
+impl IndexValue for str {
     fn as_bytes(&self) -> Vec<u8> {
         self.bytes().collect()
     }
 }
 
+impl<T: IndexValue + ?Sized> IndexValue for &T {
+    fn as_bytes(&self) -> Vec<u8> {
+        (**self).as_bytes()
+    }
+
+    fn append_element(&self, bytes: &mut Vec<u8>) {
+        (**self).append_element(bytes)
+    }
+}
+
 impl IndexValue for Cow<'_, str> {
     fn as_bytes(&self) -> Vec<u8> {
         self.bytes().collect()
@@ -29,6 +82,74 @@ impl IndexValue for ulid::Ulid {
     fn as_bytes(&self) -> Vec<u8> {
         self.0.to_be_bytes().to_vec()
     }
+
+    fixed_length!();
+}
+
+impl IndexValue for bool {
+    fn as_bytes(&self) -> Vec<u8> {
+        vec![*self as u8]
+    }
+
+    fixed_length!();
+}
+
+impl IndexValue for char {
+    fn as_bytes(&self) -> Vec<u8> {
+        (*self as u32).to_be_bytes().to_vec()
+    }
+
+    fixed_length!();
+}
+
+impl IndexValue for Duration {
+    fn as_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.as_secs().to_be_bytes().to_vec();
+        bytes.extend_from_slice(&self.subsec_nanos().to_be_bytes());
+        bytes
+    }
+
+    fixed_length!();
+}
+
+impl IndexValue for SystemTime {
+    fn as_bytes(&self) -> Vec<u8> {
+        match self.duration_since(UNIX_EPOCH) {
+            Ok(after) => {
+                let mut bytes = vec![1];
+                bytes.extend(after.as_bytes());
+                bytes
+            }
+            Err(err) => {
+                let mut bytes = vec![0];
+                bytes.extend(err.duration().as_bytes().into_iter().map(|byte| !byte));
+                bytes
+            }
+        }
+    }
+
+    fixed_length!();
+}
+
+/// Concatenates the elements, see [IndexValue::append_element].
+fn elements<'a, T: IndexValue + 'a>(items: impl IntoIterator<Item = &'a T>) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for item in items {
+        item.append_element(&mut bytes);
+    }
+    bytes
+}
+
+impl<T: IndexValue> IndexValue for [T] {
+    fn as_bytes(&self) -> Vec<u8> {
+        elements(self)
+    }
+}
+
+impl<T: IndexValue, const N: usize> IndexValue for [T; N] {
+    fn as_bytes(&self) -> Vec<u8> {
+        elements(self)
+    }
 }
 
 impl<T: IndexValue> IndexValue for Vec<T> {
@@ -49,6 +170,8 @@ macro_rules! impl_number {
             fn as_bytes(&self) -> Vec<u8> {
                 self.to_be_bytes().to_vec()
             }
+
+            fixed_length!();
         }
     };
 }
@@ -65,3 +188,182 @@ impl_number!(i128);
 impl_number!(u128);
 impl_number!(isize);
 impl_number!(usize);
+
+macro_rules! impl_float {
+    ($kind:ty, $bits:ty) => {
+        impl IndexValue for $kind {
+            fn as_bytes(&self) -> Vec<u8> {
+                let bits = self.to_bits();
+                let sign = 1 << (<$bits>::BITS - 1);
+                let bits = match bits & sign {
+                    0 => bits | sign,
+                    _ => !bits,
+                };
+                bits.to_be_bytes().to_vec()
+            }
+
+            fixed_length!();
+        }
+    };
+}
+
+impl_float!(f32, u32);
+impl_float!(f64, u64);
+
+macro_rules! impl_non_zero {
+    ($kind:ty) => {
+        impl IndexValue for $kind {
+            fn as_bytes(&self) -> Vec<u8> {
+                self.get().as_bytes()
+            }
+
+            fixed_length!();
+        }
+    };
+}
+
+impl_non_zero!(NonZeroI8);
+impl_non_zero!(NonZeroU8);
+impl_non_zero!(NonZeroI16);
+impl_non_zero!(NonZeroU16);
+impl_non_zero!(NonZeroI32);
+impl_non_zero!(NonZeroU32);
+impl_non_zero!(NonZeroI64);
+impl_non_zero!(NonZeroU64);
+impl_non_zero!(NonZeroI128);
+impl_non_zero!(NonZeroU128);
+impl_non_zero!(NonZeroIsize);
+impl_non_zero!(NonZeroUsize);
+
+macro_rules! impl_tuple {
+    ($($name:ident),+) => {
+        impl<$($name: IndexValue),+> IndexValue for ($($name,)+) {
+            #[allow(non_snake_case)]
+            fn as_bytes(&self) -> Vec<u8> {
+                let ($($name,)+) = self;
+                let mut bytes = Vec::new();
+                $($name.append_element(&mut bytes);)+
+                bytes
+            }
+        }
+    };
+}
+
+impl_tuple!(A);
+impl_tuple!(A, B);
+impl_tuple!(A, B, C);
+impl_tuple!(A, B, C, D);
+impl_tuple!(A, B, C, D, E);
+impl_tuple!(A, B, C, D, E, F);
+impl_tuple!(A, B, C, D, E, F, G);
+impl_tuple!(A, B, C, D, E, F, G, H);
+
+/// Flips the sign bit so that negative numbers sort before positive ones.
+#[cfg(any(feature = "chrono", feature = "time"))]
+fn ordered_i64(value: i64) -> [u8; 8] {
+    ((value as u64) ^ (1 << 63)).to_be_bytes()
+}
+
+#[cfg(feature = "uuid")]
+impl IndexValue for uuid::Uuid {
+    fn as_bytes(&self) -> Vec<u8> {
+        self.as_u128().to_be_bytes().to_vec()
+    }
+
+    fixed_length!();
+}
+
+#[cfg(feature = "chrono")]
+impl IndexValue for chrono::NaiveDate {
+    fn as_bytes(&self) -> Vec<u8> {
+        use chrono::Datelike;
+        ordered_i64(self.num_days_from_ce() as i64).to_vec()
+    }
+
+    fixed_length!();
+}
+
+#[cfg(feature = "chrono")]
+impl IndexValue for chrono::NaiveTime {
+    fn as_bytes(&self) -> Vec<u8> {
+        use chrono::Timelike;
+        let mut bytes = self.num_seconds_from_midnight().to_be_bytes().to_vec();
+        bytes.extend_from_slice(&self.nanosecond().to_be_bytes());
+        bytes
+    }
+
+    fixed_length!();
+}
+
+#[cfg(feature = "chrono")]
+impl IndexValue for chrono::NaiveDateTime {
+    fn as_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.date().as_bytes();
+        bytes.extend(self.time().as_bytes());
+        bytes
+    }
+
+    fixed_length!();
+}
+
+#[cfg(feature = "chrono")]
+impl<Tz: chrono::TimeZone> IndexValue for chrono::DateTime<Tz> {
+    fn as_bytes(&self) -> Vec<u8> {
+        self.naive_utc().as_bytes()
+    }
+
+    fixed_length!();
+}
+
+#[cfg(feature = "time")]
+impl IndexValue for time::Date {
+    fn as_bytes(&self) -> Vec<u8> {
+        ordered_i64(self.to_julian_day() as i64).to_vec()
+    }
+
+    fixed_length!();
+}
+
+#[cfg(feature = "time")]
+impl IndexValue for time::Time {
+    fn as_bytes(&self) -> Vec<u8> {
+        let (hour, minute, second, nanosecond) = self.as_hms_nano();
+        let mut bytes = vec![hour, minute, second];
+        bytes.extend_from_slice(&nanosecond.to_be_bytes());
+        bytes
+    }
+
+    fixed_length!();
+}
+
+#[cfg(feature = "time")]
+impl IndexValue for time::PrimitiveDateTime {
+    fn as_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.date().as_bytes();
+        bytes.extend(self.time().as_bytes());
+        bytes
+    }
+
+    fixed_length!();
+}
+
+#[cfg(feature = "time")]
+impl IndexValue for time::OffsetDateTime {
+    fn as_bytes(&self) -> Vec<u8> {
+        let utc = self.to_offset(time::UtcOffset::UTC);
+        time::PrimitiveDateTime::new(utc.date(), utc.time()).as_bytes()
+    }
+
+    fixed_length!();
+}
+
+/// Decimals are normalized first, so `1.0` and `1.00` share an index entry.
+/// The encoding does not preserve numeric order.
+#[cfg(feature = "rust_decimal")]
+impl IndexValue for rust_decimal::Decimal {
+    fn as_bytes(&self) -> Vec<u8> {
+        self.normalize().serialize().to_vec()
+    }
+
+    fixed_length!();
+}
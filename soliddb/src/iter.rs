@@ -1,23 +1,77 @@
 use std::marker::PhantomData;
 
-use rocksdb::{DBIteratorWithThreadMode, DB};
+use rocksdb::{
+    DBIteratorWithThreadMode, DBRawIteratorWithThreadMode, Direction, IteratorMode, ReadOptions,
+    SnapshotWithThreadMode,
+};
+use ulid::Ulid;
 
 use crate::{
-    keys::{id_from_primary_key, key_prefix},
-    table::from_bytes,
-    Result, Table, WithId,
+    keys::{id_from_primary_key, id_from_slice, key_prefix, prefix_end, PREFIX_LEN},
+    Database, Mode, ReadWrite, Result, Table, WithId, DB,
 };
 
 type KeyVal = (Box<[u8]>, Box<[u8]>);
 
+/// Consistent view of the database at one point in time.
+pub(crate) type Snapshot<'a> = SnapshotWithThreadMode<'a, DB>;
+
+/// Read options bounding a scan to the keys starting with the given prefix.
+///
+/// Prefixes shorter than the configured prefix extractor span several
+/// extractor prefixes and have to be scanned in total order.
+pub(crate) fn read_options(prefix: &[u8]) -> ReadOptions {
+    let mut options = ReadOptions::default();
+    match prefix.len() >= PREFIX_LEN {
+        true => options.set_prefix_same_as_start(true),
+        false => options.set_total_order_seek(true),
+    }
+    if let Some(end) = prefix_end(prefix) {
+        options.set_iterate_upper_bound(end);
+    }
+    options
+}
+
 pub(crate) struct PrefixIterator<'a> {
     prefix: Vec<u8>,
-    inner: DBIteratorWithThreadMode<'a, DB>,
+    inner: Option<DBIteratorWithThreadMode<'a, DB>>,
 }
 
 impl<'a> PrefixIterator<'a> {
-    pub(crate) fn new(db: &'a DB, prefix: Vec<u8>) -> Self {
-        let inner = db.prefix_iterator(&prefix);
+    /// Iterates over the keys of the given table starting with the prefix.
+    pub(crate) fn new<T: Table, M: Mode>(db: &'a Database<M>, prefix: Vec<u8>) -> Self {
+        let start = prefix.clone();
+        Self::starting_at::<T, M>(db, prefix, &start)
+    }
+
+    /// Iterates over the keys of the given table starting with the prefix,
+    /// beginning at the key `start`.
+    pub(crate) fn starting_at<T: Table, M: Mode>(
+        db: &'a Database<M>,
+        prefix: Vec<u8>,
+        start: &[u8],
+    ) -> Self {
+        // a missing column family holds no keys
+        let inner = db.family::<T>().map(|family| {
+            let mode = IteratorMode::From(start, Direction::Forward);
+            db.iterator_cf_opt(&family, read_options(&prefix), mode)
+        });
+        Self { prefix, inner }
+    }
+
+    /// Iterates over the keys of the given table starting with the prefix
+    /// as stored when the snapshot was taken.
+    pub(crate) fn at_snapshot<T: Table, M: Mode>(
+        db: &'a Database<M>,
+        prefix: Vec<u8>,
+        snapshot: &Snapshot<'_>,
+    ) -> Self {
+        let inner = db.family::<T>().map(|family| {
+            let mut options = read_options(&prefix);
+            options.set_snapshot(snapshot);
+            let mode = IteratorMode::From(&prefix, Direction::Forward);
+            db.iterator_cf_opt(&family, options, mode)
+        });
         Self { prefix, inner }
     }
 }
@@ -26,7 +80,7 @@ impl Iterator for PrefixIterator<'_> {
     type Item = Result<KeyVal>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let value = self.inner.next()?;
+        let value = self.inner.as_mut()?.next()?;
         let (key, bytes) = match value {
             Ok(value) => value,
             Err(err) => return Some(Err(err.into())),
@@ -38,6 +92,86 @@ impl Iterator for PrefixIterator<'_> {
         }
     }
 }
+
+/// Iterator of ids returned by [Table::ids](crate::Table::ids) and
+/// [Table::ids_by_non_unique_index](crate::Table::ids_by_non_unique_index).
+///
+/// Only keys are read, the associated values are neither fetched nor decoded.
+pub struct Ids<'a> {
+    prefix: Vec<u8>,
+    inner: Option<DBRawIteratorWithThreadMode<'a, DB>>,
+}
+
+impl<'a> Ids<'a> {
+    /// Iterates over all keys of the given table consisting of the prefix followed by an id.
+    pub(crate) fn new<T: Table, M: Mode>(db: &'a Database<M>, prefix: Vec<u8>) -> Self {
+        // a missing column family holds no keys
+        let inner = db.family::<T>().map(|family| {
+            let mut inner = db.raw_iterator_cf_opt(&family, read_options(&prefix));
+            inner.seek(&prefix);
+            inner
+        });
+        Self { prefix, inner }
+    }
+}
+
+impl Iterator for Ids<'_> {
+    type Item = Result<Ulid>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let inner = self.inner.as_mut()?;
+        loop {
+            let Some(key) = inner.key() else {
+                return inner.status().err().map(|err| Err(err.into()));
+            };
+
+            if !key.starts_with(&self.prefix) {
+                return None;
+            }
+
+            // longer values sharing this prefix belong to other entries
+            let id = match key.len() == self.prefix.len() + 16 {
+                true => Some(id_from_slice(&key[self.prefix.len()..])),
+                false => None,
+            };
+
+            inner.next();
+            if id.is_some() {
+                return id;
+            }
+        }
+    }
+}
+
+/// Iterator of Items returned by
+/// [Table::iter_by_non_unique_index](crate::Table::iter_by_non_unique_index).
+///
+/// Values are fetched and decoded one at a time.
+pub struct IndexedItems<'a, T, M: Mode = ReadWrite> {
+    db: &'a Database<M>,
+    ids: Ids<'a>,
+    _marker: PhantomData<T>,
+}
+
+impl<'a, T: Table, M: Mode> IndexedItems<'a, T, M> {
+    pub(crate) fn new(db: &'a Database<M>, ids: Ids<'a>) -> Self {
+        Self {
+            db,
+            ids,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: Table, M: Mode> Iterator for IndexedItems<'_, T, M> {
+    type Item = Result<WithId<T>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let id = self.ids.next()?;
+        Some(id.and_then(|id| T::get(self.db, id)))
+    }
+}
+
 /// Iterator of Items returned by [Table::iter](soliddb::Table::iter).
 pub struct Items<'a, T> {
     inner: PrefixIterator<'a>,
@@ -45,9 +179,9 @@ pub struct Items<'a, T> {
 }
 
 impl<'a, T: Table> Items<'a, T> {
-    pub(crate) fn new(db: &'a DB) -> Self {
+    pub(crate) fn new<M: Mode>(db: &'a Database<M>) -> Self {
         let prefix = key_prefix(T::TABLE, 0);
-        let inner = PrefixIterator::new(db, prefix);
+        let inner = PrefixIterator::new::<T, M>(db, prefix);
         Self {
             inner,
             _marker: PhantomData,
@@ -66,6 +200,6 @@ impl<T: Table> Iterator for Items<'_, T> {
 fn decode_item<T: Table>(item: Result<KeyVal>) -> Result<WithId<T>> {
     let (key, val) = item?;
     let id = id_from_primary_key(&key)?;
-    let value = from_bytes(&val)?;
+    let (value, _) = T::decode(&val)?;
     Ok(WithId { id, value })
 }
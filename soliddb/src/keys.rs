@@ -2,12 +2,28 @@ use ulid::Ulid;
 
 use crate::{Error, IndexValue, Result};
 
+/// Length of the table and index prefix shared by all keys of an index.
+pub const PREFIX_LEN: usize = 5;
+
 pub fn key_prefix(table: u32, index: u8) -> Vec<u8> {
     let mut prefix = table.to_be_bytes().to_vec();
     prefix.push(index);
     prefix
 }
 
+/// Returns the smallest key sorting after every key with the given prefix.
+/// Returns `None` if no such key exists, i.e. if the prefix only contains `0xFF`.
+pub fn prefix_end(prefix: &[u8]) -> Option<Vec<u8>> {
+    let mut end = prefix.to_vec();
+    while let Some(last) = end.pop() {
+        if last < u8::MAX {
+            end.push(last + 1);
+            return Some(end);
+        }
+    }
+    None
+}
+
 pub fn primary_key(table: u32, id: Ulid) -> Vec<u8> {
     let mut key = key_prefix(table, 0);
     key.extend_from_slice(&id.as_bytes());
@@ -32,21 +48,199 @@ pub fn non_unique_key(table: u32, index: u8, value: &[u8], id: Ulid) -> Vec<u8>
     key
 }
 
-pub fn id_from_primary_key(bytes: &[u8]) -> Result<Ulid> {
-    let bytes = bytes[5..].try_into().map_err(|_| Error::MalformedKey)?;
-    let num = u128::from_be_bytes(bytes);
-    Ok(Ulid(num))
+pub fn single_key(single: u32) -> Vec<u8> {
+    let mut key = vec![0; 4];
+    key.extend_from_slice(&single.to_be_bytes());
+    key
 }
 
-pub fn id_from_non_unique_key(bytes: &[u8]) -> Result<Ulid> {
-    let bytes = &bytes[bytes.len() - 16..];
-    let bytes = bytes.try_into().map_err(|_| Error::MalformedKey)?;
-    let num = u128::from_be_bytes(bytes);
-    Ok(Ulid(num))
+/// Byte following table 0 in catalog keys, distinguishing them from singles.
+const CATALOG: u8 = u8::MAX;
+
+/// Byte following table 0 in change feed keys.
+const CHANGE: u8 = u8::MAX - 1;
+
+/// Returns the prefix shared by all change feed keys.
+///
+/// Keys of singles numbered from `0xFE000000` share this prefix as well.
+pub fn change_prefix() -> Vec<u8> {
+    key_prefix(0, CHANGE)
+}
+
+pub fn change_key(seq: u64) -> Vec<u8> {
+    let mut key = change_prefix();
+    key.extend_from_slice(&seq.to_be_bytes());
+    key
+}
+
+/// Returns the prefix shared by all catalog keys.
+pub fn catalog_prefix() -> Vec<u8> {
+    key_prefix(0, CATALOG)
+}
+
+pub fn catalog_key(table: u32) -> Vec<u8> {
+    let mut key = catalog_prefix();
+    key.extend_from_slice(&table.to_be_bytes());
+    key
+}
+
+pub fn id_from_primary_key(bytes: &[u8]) -> Result<Ulid> {
+    match Key::parse(bytes)? {
+        Key::Primary { id, .. } => Ok(id),
+        _ => Err(malformed(bytes)),
+    }
 }
 
 pub fn id_from_slice(bytes: &[u8]) -> Result<Ulid> {
-    let bytes = bytes.try_into().map_err(|_| Error::MalformedKey)?;
+    let bytes = bytes.try_into().map_err(|_| malformed(bytes))?;
     let num = u128::from_be_bytes(bytes);
     Ok(Ulid(num))
 }
+
+/// A decoded database key.
+///
+/// Keys start with the big-endian table number followed by the index number.
+/// Index 0 holds the stored values, indices below 128 are unique and all
+/// others are non-unique. Singles use table 0 followed by their number,
+/// catalog entries table 0 followed by `0xFF` and the number of the table
+/// and change feed events table 0 followed by `0xFE` and their sequence number.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Key {
+    /// Key of a stored value.
+    Primary {
+        /// Number of the table.
+        table: u32,
+        /// Id of the entry.
+        id: Ulid,
+    },
+
+    /// Key of a unique index entry. The id is stored as value.
+    Unique {
+        /// Number of the table.
+        table: u32,
+        /// Number of the index.
+        index: u8,
+        /// Byte representation of the indexed value.
+        value: Vec<u8>,
+    },
+
+    /// Key of a non-unique index entry.
+    NonUnique {
+        /// Number of the table.
+        table: u32,
+        /// Number of the index.
+        index: u8,
+        /// Byte representation of the indexed value.
+        value: Vec<u8>,
+        /// Id of the referenced entry.
+        id: Ulid,
+    },
+
+    /// Key of a single.
+    Single {
+        /// Number of the single.
+        single: u32,
+    },
+
+    /// Key of the catalog entry of a table.
+    Catalog {
+        /// Number of the table.
+        table: u32,
+    },
+
+    /// Key of an event of the change feed.
+    Change {
+        /// Sequence number of the event.
+        seq: u64,
+    },
+}
+
+impl Key {
+    /// Decodes the given raw key, failing with [Error::MalformedKey]
+    /// if it does not match any of the known layouts.
+    pub fn parse(bytes: &[u8]) -> Result<Key> {
+        let Some((table, rest)) = split_u32(bytes) else {
+            return Err(malformed(bytes));
+        };
+
+        if table == 0 {
+            return match (split_u32(rest), rest.split_first()) {
+                (Some((single, [])), _) => Ok(Key::Single { single }),
+                (_, Some((&CATALOG, rest))) => match split_u32(rest) {
+                    Some((table, [])) => Ok(Key::Catalog { table }),
+                    _ => Err(malformed(bytes)),
+                },
+                (_, Some((&CHANGE, rest))) => match <[u8; 8]>::try_from(rest) {
+                    Ok(seq) => Ok(Key::Change {
+                        seq: u64::from_be_bytes(seq),
+                    }),
+                    Err(_) => Err(malformed(bytes)),
+                },
+                _ => Err(malformed(bytes)),
+            };
+        }
+
+        let Some((&index, rest)) = rest.split_first() else {
+            return Err(malformed(bytes));
+        };
+
+        match index {
+            0 => {
+                let id = id_from_slice(rest).map_err(|_| malformed(bytes))?;
+                Ok(Key::Primary { table, id })
+            }
+            1..=127 => Ok(Key::Unique {
+                table,
+                index,
+                value: rest.to_vec(),
+            }),
+            _ => {
+                let Some(split) = rest.len().checked_sub(16) else {
+                    return Err(malformed(bytes));
+                };
+                let (value, id) = rest.split_at(split);
+                Ok(Key::NonUnique {
+                    table,
+                    index,
+                    value: value.to_vec(),
+                    id: id_from_slice(id)?,
+                })
+            }
+        }
+    }
+
+    /// Encodes this key into its raw representation.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            Key::Primary { table, id } => primary_key(*table, *id),
+            Key::Unique {
+                table,
+                index,
+                value,
+            } => unique_key(*table, *index, value),
+            Key::NonUnique {
+                table,
+                index,
+                value,
+                id,
+            } => non_unique_key(*table, *index, value, *id),
+            Key::Single { single } => single_key(*single),
+            Key::Catalog { table } => catalog_key(*table),
+            Key::Change { seq } => change_key(*seq),
+        }
+    }
+}
+
+fn split_u32(bytes: &[u8]) -> Option<(u32, &[u8])> {
+    if bytes.len() < 4 {
+        return None;
+    }
+    let (number, rest) = bytes.split_at(4);
+    Some((u32::from_be_bytes(number.try_into().ok()?), rest))
+}
+
+fn malformed(bytes: &[u8]) -> Error {
+    Error::MalformedKey {
+        bytes: bytes.to_vec(),
+    }
+}
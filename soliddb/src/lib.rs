@@ -3,28 +3,72 @@
 //! This crate provides traits for storing serializable types
 //! in RocksDB.
 
+mod backup;
+mod catalog;
+mod changes;
+mod database;
 mod error;
+mod export;
+mod hooks;
 mod index;
 mod iter;
 mod keys;
+mod migrate;
+mod registry;
+mod relocate;
 mod single;
+mod subscribe;
 mod table;
+mod verify;
 
-pub use error::{Error, Result};
+pub use backup::{list_backups, purge_old_backups, restore_backup, restore_from, Backup};
+pub use catalog::{IndexInfo, SchemaDiff, TableInfo};
+pub use changes::{ChangeEvent, Changes, Op};
+pub use database::{Compression, Database, Mode, OpenOptions, ReadOnly, ReadWrite, Secondary, DB};
+pub use error::{Error, ErrorKind, Result};
+pub use export::{Dump, DumpFns, Format};
+pub use hooks::{Hooks, WriteContext};
 pub use index::IndexValue;
-pub use iter::Items;
+pub use iter::{Ids, IndexedItems, Items};
+pub use keys::Key;
+pub use migrate::{migrate_table, schema_version, Migrator};
+pub use registry::{registrations, Claim, Registration};
 pub use single::Single;
+pub use subscribe::{Change, SingleChange};
 pub use table::{Table, WithId};
+pub use verify::{IndexEntry, Report};
 
-pub use rocksdb::DB;
-pub use soliddb_derive::{Single, Table};
+pub use soliddb_derive::{IndexValue, Single, Table};
+
+#[doc(hidden)]
+pub use inventory;
+#[doc(hidden)]
+pub use ulid;
 
 /// Opens a new RocksDB Database at the given path.
 ///
 /// This function ensures that the directory exists before
 /// opening the database with default parameters.
-pub fn open<P: AsRef<std::path::Path>>(path: P) -> Result<DB> {
-    std::fs::create_dir_all(path.as_ref()).map_err(Error::CreateDirectory)?;
-    let db = DB::open_default(path.as_ref())?;
-    Ok(db)
+/// Use [OpenOptions] to configure the database.
+pub fn open<P: AsRef<std::path::Path>>(path: P) -> Result<Database> {
+    Database::open(path)
+}
+
+/// Opens the RocksDB Database at the given path without taking its lock.
+///
+/// Write methods are not available on the returned handle.
+pub fn open_read_only<P: AsRef<std::path::Path>>(path: P) -> Result<Database<ReadOnly>> {
+    OpenOptions::new().open_read_only(path)
+}
+
+/// Opens the RocksDB Database at `primary` as secondary instance,
+/// storing its own logs in `secondary`.
+///
+/// Use [Database::try_catch_up_with_primary] to read the latest changes.
+/// Write methods of tables and singles are not available on the returned handle.
+pub fn open_as_secondary<P: AsRef<std::path::Path>, Q: AsRef<std::path::Path>>(
+    primary: P,
+    secondary: Q,
+) -> Result<Database<Secondary>> {
+    OpenOptions::new().open_as_secondary(primary, secondary)
 }
@@ -0,0 +1,207 @@
+use rocksdb::WriteBatch;
+use serde::{Deserialize, Serialize};
+use ulid::Ulid;
+
+use crate::iter::PrefixIterator;
+use crate::keys::{id_from_primary_key, key_prefix, prefix_end, primary_key, single_key};
+use crate::table::{to_bytes, REBUILD_BATCH_SIZE};
+use crate::{Database, Error, Mode, ReadWrite, Result, Single, Table};
+
+/// Schema version applied by a [Migrator], stored in the reserved single `u32::MAX`.
+#[derive(Serialize, Deserialize)]
+struct SchemaVersion(u32);
+
+impl Single for SchemaVersion {
+    const SINGLE: u32 = u32::MAX;
+    const NAME: &'static str = "SchemaVersion";
+}
+
+/// Progress of an interrupted [migrate_table], stored in the reserved single `u32::MAX - 1`.
+#[derive(Serialize, Deserialize)]
+struct MigrationProgress {
+    /// Tables of the old and the new type.
+    tables: (u32, u32),
+    /// Id of the last converted row.
+    last: Ulid,
+    /// Number of rows converted so far.
+    rows: usize,
+}
+
+impl Single for MigrationProgress {
+    const SINGLE: u32 = u32::MAX - 1;
+    const NAME: &'static str = "MigrationProgress";
+}
+
+type Step = Box<dyn Fn(&Database) -> Result<()> + Send + Sync>;
+
+/// Ordered list of migration steps, each applied once per database.
+///
+/// The schema version of a database is the number of steps applied to it.
+/// Steps are numbered in the order they are added, so new steps must
+/// only ever be appended. The version is stored after every step,
+/// a step failing or interrupted by a crash is run again by the next [Migrator::run].
+///
+/// ```no_run
+/// # use serde::{Deserialize, Serialize};
+/// # use soliddb::{Migrator, Table};
+/// # #[derive(Serialize, Deserialize, Table)]
+/// # #[solid(table = 1, version = 1)]
+/// # struct UserV1 { name: String }
+/// # #[derive(Serialize, Deserialize, Table)]
+/// # #[solid(table = 1, version = 2)]
+/// # struct UserV2 { name: String, admin: bool }
+/// # fn main() -> soliddb::Result<()> {
+/// let db = soliddb::open("/tmp/soliddb")?;
+/// Migrator::new()
+///     .step(|db| {
+///         soliddb::migrate_table::<UserV1, UserV2>(db, |old| UserV2 {
+///             name: old.name,
+///             admin: false,
+///         })?;
+///         Ok(())
+///     })
+///     .run(&db)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Default)]
+pub struct Migrator {
+    steps: Vec<Step>,
+}
+
+impl Migrator {
+    /// Creates a migrator without any steps.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a step migrating to the next version.
+    pub fn step<F>(mut self, step: F) -> Self
+    where
+        F: Fn(&Database) -> Result<()> + Send + Sync + 'static,
+    {
+        self.steps.push(Box::new(step));
+        self
+    }
+
+    /// Returns the version the steps migrate to.
+    pub fn latest(&self) -> u32 {
+        self.steps.len() as u32
+    }
+
+    /// Runs all steps not applied to the database yet, returning the new version.
+    ///
+    /// Fails with [Error::UnknownVersion] if the database was migrated
+    /// by a newer migrator with more steps.
+    pub fn run(&self, db: &Database) -> Result<u32> {
+        let version = schema_version(db)?;
+        if version > self.latest() {
+            return Err(Error::UnknownVersion {
+                version,
+                latest: self.latest(),
+            });
+        }
+
+        for (step, version) in self.steps.iter().zip(1..).skip(version as usize) {
+            step(db)?;
+            SchemaVersion(version).put(db)?;
+        }
+
+        Ok(self.latest())
+    }
+}
+
+impl std::fmt::Debug for Migrator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Migrator")
+            .field("latest", &self.latest())
+            .finish()
+    }
+}
+
+/// Returns the version applied by [Migrator::run], 0 if it never ran.
+pub fn schema_version<M: Mode>(db: &Database<M>) -> Result<u32> {
+    match SchemaVersion::get(db) {
+        Ok(SchemaVersion(version)) => Ok(version),
+        Err(Error::NotFound { .. }) => Ok(0),
+        Err(err) => Err(err),
+    }
+}
+
+/// Converts every row of `Old` with the given function and stores it as `New`
+/// with the same id, returning the number of converted rows.
+///
+/// Rows are written in batches and the indices of `New` are rebuilt afterwards.
+/// Both types usually share their table number as different versions,
+/// otherwise the table of `Old` is truncated.
+///
+/// Every batch stores the id of its last row, so a migration interrupted by
+/// an error or a crash continues after the converted rows when it is run again.
+pub fn migrate_table<Old: Table, New: Table>(
+    db: &Database,
+    mut convert: impl FnMut(Old) -> New,
+) -> Result<usize> {
+    let tables = (Old::TABLE, New::TABLE);
+    let mut progress = match MigrationProgress::get(db) {
+        Ok(progress) if progress.tables == tables => Some(progress),
+        Ok(_) | Err(Error::NotFound { .. }) => None,
+        Err(err) => return Err(err),
+    };
+
+    let prefix = key_prefix(Old::TABLE, 0);
+    let start = match &progress {
+        Some(progress) => match progress.last.0.checked_add(1) {
+            Some(next) => primary_key(Old::TABLE, Ulid(next)),
+            // the last possible id was converted, the primary prefix ends with index 0
+            None => prefix_end(&prefix).unwrap_or_default(),
+        },
+        None => prefix.clone(),
+    };
+
+    let family = db.write_family::<New>()?;
+    let mut batch = WriteBatch::default();
+    let mut rows = progress.as_ref().map_or(0, |progress| progress.rows);
+
+    for item in PrefixIterator::starting_at::<Old, ReadWrite>(db, prefix, &start) {
+        let (key, bytes) = item?;
+        let id = id_from_primary_key(&key)?;
+        let (value, _) = Old::decode(&bytes)?;
+        batch.put_cf(
+            &family,
+            primary_key(New::TABLE, id),
+            to_bytes(New::NAME, &convert(value))?,
+        );
+
+        rows += 1;
+        progress = Some(MigrationProgress {
+            tables,
+            last: id,
+            rows,
+        });
+        if rows % REBUILD_BATCH_SIZE == 0 {
+            write_progress(db, &mut batch, progress.as_ref())?;
+        }
+    }
+    write_progress(db, &mut batch, progress.as_ref())?;
+
+    if Old::TABLE != New::TABLE {
+        Old::truncate(db)?;
+    }
+    New::rebuild_indexes(db)?;
+    MigrationProgress::delete(db)?;
+    Ok(rows)
+}
+
+/// Writes the converted rows of the batch together with the progress.
+fn write_progress(
+    db: &Database,
+    batch: &mut WriteBatch,
+    progress: Option<&MigrationProgress>,
+) -> Result<()> {
+    let mut batch = std::mem::take(batch);
+    if let Some(progress) = progress {
+        let bytes = to_bytes(MigrationProgress::NAME, progress)?;
+        batch.put(single_key(MigrationProgress::SINGLE), bytes);
+    }
+    db.write(batch)
+}
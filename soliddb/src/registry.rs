@@ -0,0 +1,180 @@
+use crate::keys::single_key;
+use crate::{Database, DumpFns, Error, Mode, OpenOptions, Result, TableInfo};
+
+/// Kind of number claimed by a type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Claim {
+    /// Number of a [Table](crate::Table).
+    Table,
+    /// Number of a [Single](crate::Single).
+    Single,
+}
+
+impl std::fmt::Display for Claim {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Claim::Table => f.write_str("table"),
+            Claim::Single => f.write_str("single"),
+        }
+    }
+}
+
+/// Number claimed by a type deriving [Table](crate::Table) or [Single](crate::Single).
+///
+/// Registered by the derive macros and collected from all linked crates,
+/// see [OpenOptions::check_collisions].
+/// Two types of one crate claiming the same number without a version fail to compile,
+/// even in different modules:
+///
+/// ```compile_fail
+/// # use serde::{Deserialize, Serialize};
+/// # use soliddb::Table;
+/// mod users {
+///     use serde::{Deserialize, Serialize};
+///     use soliddb::Table;
+///
+///     #[derive(Serialize, Deserialize, Table)]
+///     #[solid(table = 1)]
+///     struct User {}
+/// }
+///
+/// #[derive(Serialize, Deserialize, Table)]
+/// #[solid(table = 1)]
+/// struct Account {}
+/// ```
+///
+/// So do two types declaring the same version of a number:
+///
+/// ```compile_fail
+/// # use serde::{Deserialize, Serialize};
+/// # use soliddb::Table;
+/// #[derive(Serialize, Deserialize, Table)]
+/// #[solid(table = 1, version = 1)]
+/// struct User {}
+///
+/// #[derive(Serialize, Deserialize, Table)]
+/// #[solid(table = 1, version = 1)]
+/// struct Account {}
+/// ```
+///
+/// Several versions of a table can share its number:
+///
+/// ```
+/// # use serde::{Deserialize, Serialize};
+/// # use soliddb::Table;
+/// #[derive(Serialize, Deserialize, Table)]
+/// #[solid(table = 1, version = 1)]
+/// struct UserV1 {}
+///
+/// #[derive(Serialize, Deserialize, Table)]
+/// #[solid(table = 1, version = 2)]
+/// struct UserV2 {}
+/// ```
+#[derive(Debug)]
+pub struct Registration {
+    /// Whether a table or a single number is claimed.
+    pub claim: Claim,
+
+    /// The claimed number.
+    pub id: u32,
+
+    /// The `version` attribute, which allows several
+    /// versions of the same table or single.
+    pub version: Option<u32>,
+
+    /// Full path of the type.
+    pub path: &'static str,
+
+    /// Returns the catalog entry of a table, `None` for singles.
+    pub info: Option<fn(&OpenOptions) -> TableInfo>,
+
+    /// Rebuilds the indices of a table, `None` for singles.
+    /// See [OpenOptions::rebuild_indexes].
+    pub rebuild: Option<fn(&Database) -> Result<()>>,
+
+    /// Adds the table or single to a [Dump](crate::Dump), see [Dump::all](crate::Dump::all).
+    pub dump: DumpFns,
+}
+
+inventory::collect!(Registration);
+
+impl Registration {
+    fn collides_with(&self, other: &Registration) -> bool {
+        self.claim == other.claim
+            && self.id == other.id
+            && (self.version.is_none() || self.version == other.version)
+    }
+}
+
+/// Returns all registered tables and singles, ordered by their number.
+pub fn registrations() -> Vec<&'static Registration> {
+    let mut registrations: Vec<_> = inventory::iter::<Registration>.into_iter().collect();
+    registrations.sort_by_key(|registration| (registration.claim, registration.id));
+    registrations
+}
+
+/// Returns the latest registered version of the table which stored the given catalog entry.
+///
+/// Types of other databases may claim the same number. The type named by the entry
+/// and its other versions are used, otherwise the only type or set of versions claiming
+/// the number. Returns `None` if no or several unrelated types claim it.
+pub(crate) fn table_of(options: &OpenOptions, stored: &TableInfo) -> Option<&'static Registration> {
+    let claims: Vec<_> = registrations()
+        .into_iter()
+        .filter(|registration| {
+            registration.claim == Claim::Table && registration.id == stored.table
+        })
+        .collect();
+    let writer = claims.iter().find(|registration| {
+        let info = registration.info.map(|info| info(options));
+        info.is_some_and(|info| info.name == stored.name)
+    });
+
+    let versioned = claims
+        .iter()
+        .all(|registration| registration.version.is_some());
+    let family: Vec<_> = match writer {
+        Some(writer) if writer.version.is_none() => vec![*writer],
+        Some(_) => claims
+            .into_iter()
+            .filter(|registration| registration.version.is_some())
+            .collect(),
+        None if versioned || claims.len() == 1 => claims,
+        None => return None,
+    };
+    family
+        .into_iter()
+        .max_by_key(|registration| registration.version)
+}
+
+/// Fails with [Error::Collision] if two types claim a number stored
+/// in the given database without declaring different versions.
+pub(crate) fn check<M: Mode>(db: &Database<M>) -> Result<()> {
+    let registrations = registrations();
+    for (position, first) in registrations.iter().enumerate() {
+        let same_id = registrations[position + 1..]
+            .iter()
+            .take_while(|other| (other.claim, other.id) == (first.claim, first.id));
+        for second in same_id {
+            let collides = first.collides_with(second) || second.collides_with(first);
+            if collides && is_stored(db, first)? {
+                return Err(Error::Collision {
+                    claim: first.claim,
+                    id: first.id,
+                    first: first.path,
+                    second: second.path,
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns true if the number of the registration is used in the given database.
+fn is_stored<M: Mode>(db: &Database<M>, registration: &Registration) -> Result<bool> {
+    match registration.claim {
+        Claim::Table => Ok(db.table_info(registration.id)?.is_some()),
+        Claim::Single => Ok(db.get_pinned(single_key(registration.id))?.is_some()),
+    }
+}
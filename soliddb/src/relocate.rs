@@ -0,0 +1,79 @@
+use rocksdb::{Direction, IteratorMode, WriteBatch};
+
+use crate::database::Family;
+use crate::iter::{read_options, PrefixIterator};
+use crate::keys::catalog_key;
+use crate::table::{to_bytes, REBUILD_BATCH_SIZE};
+use crate::{Database, Error, ReadWrite, Result, Table, TableInfo};
+
+/// See [Table::move_to_with_progress].
+pub(crate) fn move_to<T: Table>(
+    db: &Database,
+    table: u32,
+    mut progress: impl FnMut(usize),
+) -> Result<usize> {
+    if table == T::TABLE {
+        return Ok(0);
+    }
+    // table 0 holds the singles and the catalog
+    if table == 0 {
+        return Err(Error::TableInUse { table });
+    }
+
+    let source = db.write_family::<T>()?;
+    let name = db.config().column_family_of::<T>(table);
+    let target = db.create_family(&name)?;
+
+    let mut info = TableInfo::of::<T, ReadWrite>(db);
+    info.table = table;
+    info.column_family = name.into_owned();
+
+    // the catalog entry of the target is written first,
+    // so an interrupted move is recognized and resumed
+    let stored = db.table_info(table)?;
+    let resuming = stored
+        .as_ref()
+        .is_some_and(|stored| stored.name == info.name && stored.fingerprint == info.fingerprint);
+    if !resuming && (stored.is_some() || has_keys(db, &target, table)?) {
+        return Err(Error::TableInUse { table });
+    }
+    if stored.as_ref() != Some(&info) {
+        let bytes = to_bytes("TableInfo", &info)?;
+        db.put_opt(catalog_key(table), bytes, db.write_options())?;
+    }
+
+    let prefix = T::TABLE.to_be_bytes();
+    let mut batch = WriteBatch::default();
+    let mut moved: usize = 0;
+    for item in PrefixIterator::new::<T, ReadWrite>(db, prefix.to_vec()) {
+        let (key, bytes) = item?;
+        let mut moved_key = key.to_vec();
+        moved_key[..prefix.len()].copy_from_slice(&table.to_be_bytes());
+        batch.put_cf(&target, moved_key, bytes);
+        batch.delete_cf(&source, key);
+
+        moved += 1;
+        if moved % REBUILD_BATCH_SIZE == 0 {
+            db.write(std::mem::take(&mut batch))?;
+            progress(moved);
+        }
+    }
+    db.write(batch)?;
+    progress(moved);
+
+    db.unrecord::<T>()?;
+    Ok(moved)
+}
+
+/// Returns true if the given column family holds keys of the given table.
+fn has_keys(db: &Database, family: &Family<'_>, table: u32) -> Result<bool> {
+    let prefix = table.to_be_bytes();
+    let mode = IteratorMode::From(&prefix, Direction::Forward);
+    match db
+        .iterator_cf_opt(family, read_options(&prefix), mode)
+        .next()
+    {
+        Some(item) => Ok(item?.0.starts_with(&prefix)),
+        None => Ok(false),
+    }
+}
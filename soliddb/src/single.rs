@@ -1,38 +1,70 @@
-use rocksdb::DB;
+use std::sync::mpsc::Receiver;
+
+use rocksdb::WriteBatch;
 use serde::{de::DeserializeOwned, Serialize};
 
+use crate::changes::Record;
+use crate::keys::single_key;
 use crate::table::{from_bytes, to_bytes};
-use crate::{Error, Result};
+use crate::{subscribe, Database, Error, Mode, Result, SingleChange};
 
 /// Trait for storing a single instance of the given type
 /// in a rocksdb database instance. Can be derived.
 pub trait Single: Serialize + DeserializeOwned {
     /// Number uniquely identifying the type.
+    /// Numbers from `0xFE000000` are reserved.
     const SINGLE: u32;
 
+    /// Name of the type used in errors. Derived implementations use the name of the type.
+    const NAME: &'static str = "unnamed single";
+
     /// Stores the value in the given db.
-    fn put(&self, db: &DB) -> Result<()> {
-        let serialized = to_bytes(self)?;
-        db.put(key(Self::SINGLE), serialized)?;
-        Ok(())
+    fn put(&self, db: &Database) -> Result<()> {
+        let key = single_key(Self::SINGLE);
+        let serialized = to_bytes(Self::NAME, self)?;
+        let mut batch = WriteBatch::default();
+        batch.put(&key, &serialized);
+
+        db.write_change(batch, || {
+            let before = db.get_pinned(&key)?;
+            let record = Record::single(Self::SINGLE, before.as_deref(), Some(&serialized));
+            Ok(Some(record))
+        })
     }
 
     /// Retrieve the stored value from the given db.
-    fn get(db: &DB) -> Result<Self> {
-        let bytes = db.get_pinned(key(Self::SINGLE))?.ok_or(Error::NotFound)?;
-        let value = from_bytes(&bytes)?;
+    fn get<M: Mode>(db: &Database<M>) -> Result<Self> {
+        let bytes = db
+            .get_pinned(single_key(Self::SINGLE))?
+            .ok_or(Error::NotFound {
+                table: Self::NAME,
+                id: None,
+            })?;
+        let value = from_bytes(Self::NAME, &bytes)?;
         Ok(value)
     }
 
-    /// Delete the stored value from the given db.
-    fn delete(db: &DB) -> Result<()> {
-        db.delete(key(Self::SINGLE))?;
-        Ok(())
+    /// Returns a receiver of all writes made by [Single::put] and [Single::delete]
+    /// through this handle, delivered once they are stored.
+    ///
+    /// The subscription ends when the receiver is dropped.
+    fn watch(db: &Database) -> Receiver<SingleChange<Self>>
+    where
+        Self: Send + 'static,
+    {
+        subscribe::watch(db)
     }
-}
 
-fn key(single: u32) -> Vec<u8> {
-    let mut key = vec![0; 4];
-    key.extend_from_slice(&single.to_be_bytes());
-    key
+    /// Delete the stored value from the given db.
+    fn delete(db: &Database) -> Result<()> {
+        let key = single_key(Self::SINGLE);
+        let mut batch = WriteBatch::default();
+        batch.delete(&key);
+
+        db.write_change(batch, || {
+            // deleting a missing single changes nothing
+            let before = db.get_pinned(&key)?;
+            Ok(before.map(|before| Record::single(Self::SINGLE, Some(&before), None)))
+        })
+    }
 }
@@ -0,0 +1,131 @@
+use std::sync::mpsc::{self, Receiver};
+
+use serde::de::DeserializeOwned;
+use ulid::Ulid;
+
+use crate::changes::Record;
+use crate::table::from_bytes;
+use crate::{Database, Result, Single, Table, WithId};
+
+/// Write of a table row delivered by [Table::subscribe].
+#[derive(Debug)]
+pub enum Change<T> {
+    /// A value was stored for a new id.
+    Created(WithId<T>),
+    /// A stored value was replaced.
+    Updated {
+        /// The value before the update.
+        old: WithId<T>,
+        /// The value after the update.
+        new: WithId<T>,
+    },
+    /// A stored value was deleted.
+    Deleted(WithId<T>),
+}
+
+impl<T> Change<T> {
+    /// Returns the id of the changed row.
+    pub fn id(&self) -> Ulid {
+        match self {
+            Change::Created(item) | Change::Updated { new: item, .. } | Change::Deleted(item) => {
+                item.id
+            }
+        }
+    }
+}
+
+/// Write of a single delivered by [Single::watch].
+#[derive(Debug)]
+pub enum SingleChange<S> {
+    /// The single was stored for the first time.
+    Created(S),
+    /// The stored single was replaced.
+    Updated {
+        /// The value before the update.
+        old: S,
+        /// The value after the update.
+        new: S,
+    },
+    /// The stored single was deleted.
+    Deleted(S),
+}
+
+/// See [Table::subscribe] and [Table::subscribe_by_index].
+pub(crate) fn subscribe<T: Table + Send + 'static>(
+    db: &Database,
+    filter: Option<(u8, Vec<u8>)>,
+) -> Receiver<Change<T>> {
+    let (sender, receiver) = mpsc::channel();
+    db.change_log().subscribe(Box::new(move |record: &Record| {
+        let Some(id) = record.id.filter(|_| record.table == Some(T::TABLE)) else {
+            return true;
+        };
+        // values of other versions sharing the table number are skipped
+        let Ok((before, after)) = decode::<T>(T::NAME, record) else {
+            return true;
+        };
+
+        if let Some((index, value)) = &filter {
+            let matches = |item: &T| index_value(item, *index).is_ok_and(|v| v == *value);
+            if !before.iter().chain(&after).any(matches) {
+                return true;
+            }
+        }
+
+        let with_id = |value| WithId { id, value };
+        let change = match (before, after) {
+            (None, Some(new)) => Change::Created(with_id(new)),
+            (Some(old), Some(new)) => Change::Updated {
+                old: with_id(old),
+                new: with_id(new),
+            },
+            (Some(old), None) => Change::Deleted(with_id(old)),
+            (None, None) => return true,
+        };
+        sender.send(change).is_ok()
+    }));
+
+    receiver
+}
+
+/// See [Single::watch].
+pub(crate) fn watch<S: Single + Send + 'static>(db: &Database) -> Receiver<SingleChange<S>> {
+    let (sender, receiver) = mpsc::channel();
+    db.change_log().subscribe(Box::new(move |record: &Record| {
+        if record.single != Some(S::SINGLE) {
+            return true;
+        }
+        let Ok((before, after)) = decode::<S>(S::NAME, record) else {
+            return true;
+        };
+
+        let change = match (before, after) {
+            (None, Some(new)) => SingleChange::Created(new),
+            (Some(old), Some(new)) => SingleChange::Updated { old, new },
+            (Some(old), None) => SingleChange::Deleted(old),
+            (None, None) => return true,
+        };
+        sender.send(change).is_ok()
+    }));
+
+    receiver
+}
+
+/// Decodes the values before and after the recorded write.
+fn decode<T: DeserializeOwned>(
+    name: &'static str,
+    record: &Record,
+) -> Result<(Option<T>, Option<T>)> {
+    let decode = |text: &String| from_bytes(name, text.as_bytes());
+    let before = record.before.as_ref().map(decode).transpose()?;
+    let after = record.after.as_ref().map(decode).transpose()?;
+    Ok((before, after))
+}
+
+/// Returns the byte representation of the given unique or non-unique index.
+fn index_value<T: Table>(item: &T, index: u8) -> Result<Vec<u8>> {
+    match T::UNIQUE_INDICES.contains(&index) {
+        true => item.unique_value(index),
+        false => item.non_unique_value(index),
+    }
+}
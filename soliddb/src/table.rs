@@ -1,12 +1,22 @@
-use rocksdb::{WriteBatch, DB};
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::sync::mpsc::Receiver;
+
+use rocksdb::{WriteBatch, DEFAULT_COLUMN_FAMILY_NAME};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use ulid::Ulid;
 
+use crate::changes::Record;
+use crate::database::Family;
 use crate::iter::PrefixIterator;
 use crate::keys::{
-    id_from_non_unique_key, id_from_slice, key_prefix, non_unique_key, primary_key, unique_key,
+    id_from_primary_key, id_from_slice, key_prefix, non_unique_key, non_unique_prefix, prefix_end,
+    primary_key, unique_key,
+};
+use crate::{
+    export, relocate, subscribe, verify, Change, Database, Error, Format, Ids, IndexValue,
+    IndexedItems, Items, Mode, ReadWrite, Report, Result, WriteContext,
 };
-use crate::{Error, IndexValue, Items, Result};
 
 /// Trait for storing a collection  of instances instance
 /// of the given type in a rocksdb database instance. Can be derived.
@@ -14,181 +24,584 @@ pub trait Table: Serialize + DeserializeOwned {
     /// Number uniquely identifying the type.
     const TABLE: u32;
 
+    /// Name of the type used in errors. Derived implementations use the name of the type.
+    const NAME: &'static str = "unnamed table";
+
+    /// Column family storing the values and indices of this table.
+    /// See [Database::column_family].
+    const COLUMN_FAMILY: Option<&'static str> = None;
+
+    /// Names of the fields of a struct, or the variants of an enum.
+    /// Recorded in the catalog, see [TableInfo](crate::TableInfo).
+    const FIELDS: &'static [&'static str] = &[];
+
+    /// Hash of the fields with their types and the indices, stored in the catalog
+    /// to detect incompatible changes, see [SchemaDiff](crate::SchemaDiff).
+    const FINGERPRINT: u64 = 0;
+
     /// List of unique indices.
     const UNIQUE_INDICES: &'static [u8] = &[];
 
     /// List of non-unique indices.
     const NON_UNIQUE_INDICES: &'static [u8] = &[];
 
-    /// Returns a byte representation for the given unique index.
-    fn unique_value(&self, index: u8) -> Vec<u8> {
-        unreachable!("no unique value for index {index}")
+    /// Returns the name of the field the given index is derived from.
+    fn index_name(index: u8) -> Option<&'static str> {
+        let _ = index;
+        None
     }
 
-    /// Returns a byte representation for the given non-unique index.
-    fn non_unique_value(&self, index: u8) -> Vec<u8> {
-        unreachable!("no non-unique value for index {index}")
+    /// Converts a value stored by an older version of this table,
+    /// returning `None` if there is no older version.
+    /// Derived from the `upgrade_from` attribute, which requires
+    /// a [From] implementation for the older [Table].
+    fn upgrade(bytes: &[u8]) -> Option<Result<Self>> {
+        let _ = bytes;
+        None
     }
 
-    /// Storing this value in the given db returning the id.
-    fn create(&self, db: &DB) -> Result<Ulid> {
-        check_unique(db, self)?;
-
-        let mut batch = WriteBatch::default();
-
-        let id = Ulid::new();
-        let key = primary_key(Self::TABLE, id);
-        let serialized = to_bytes(self)?;
+    /// Returns the index keys of a value stored by the older version this table upgrades from,
+    /// `None` if there is no older version. Derived from the `upgrade_from` attribute.
+    fn upgrade_index_keys(bytes: &[u8], id: Ulid) -> Option<Result<Vec<Vec<u8>>>> {
+        let _ = (bytes, id);
+        None
+    }
 
-        batch.put(key, serialized);
+    /// Returns the index keys stored along with a value by the version that stored it.
+    fn stored_index_keys(bytes: &[u8], id: Ulid) -> Result<Vec<Vec<u8>>> {
+        let err = match from_bytes::<Self>(Self::NAME, bytes) {
+            Ok(value) => return index_keys(&value, id),
+            Err(err) => err,
+        };
 
-        for index in Self::UNIQUE_INDICES {
-            let value = self.unique_value(*index);
-            let key = unique_key(Self::TABLE, *index, &value);
-            batch.put(key, id.as_bytes());
+        match Self::upgrade_index_keys(bytes, id) {
+            Some(Ok(keys)) => Ok(keys),
+            Some(Err(_)) | None => Err(err),
         }
+    }
 
-        for index in Self::NON_UNIQUE_INDICES {
-            let value = self.non_unique_value(*index);
-            let key = non_unique_key(Self::TABLE, *index, &value, id);
-            batch.put(key, vec![]);
+    /// Decodes a stored value, upgrading it if it was stored by an older version.
+    /// Returns the value and whether it was upgraded.
+    fn decode(bytes: &[u8]) -> Result<(Self, bool)> {
+        let err = match from_bytes(Self::NAME, bytes) {
+            Ok(value) => return Ok((value, false)),
+            Err(err) => err,
+        };
+
+        match Self::upgrade(bytes) {
+            Some(Ok(value)) => Ok((value, true)),
+            // the error of the current version is the relevant one
+            Some(Err(_)) | None => Err(err),
         }
+    }
 
-        db.write(batch)?;
+    /// Calls [Hooks::before_create](crate::Hooks::before_create) on a copy of this value,
+    /// returning the copy to store, `None` without hooks. Derived from the `hooks` attribute.
+    fn before_create_hook(&self, ctx: &mut WriteContext<'_>) -> Result<Option<Self>> {
+        let _ = ctx;
+        Ok(None)
+    }
+
+    /// Calls [Hooks::after_create](crate::Hooks::after_create). Derived from the `hooks` attribute.
+    fn after_create_hook(&self, id: Ulid) {
+        let _ = id;
+    }
+
+    /// Calls [Hooks::before_update](crate::Hooks::before_update) on a copy of this value,
+    /// returning the copy to store, `None` without hooks. Derived from the `hooks` attribute.
+    fn before_update_hook(
+        &self,
+        old: &WithId<Self>,
+        ctx: &mut WriteContext<'_>,
+    ) -> Result<Option<Self>> {
+        let _ = (old, ctx);
+        Ok(None)
+    }
+
+    /// Calls [Hooks::after_update](crate::Hooks::after_update). Derived from the `hooks` attribute.
+    fn after_update_hook(&self, old: &WithId<Self>) {
+        let _ = old;
+    }
+
+    /// Calls [Hooks::before_delete](crate::Hooks::before_delete). Derived from the `hooks` attribute.
+    fn before_delete_hook(item: &WithId<Self>, ctx: &mut WriteContext<'_>) -> Result<()> {
+        let _ = (item, ctx);
+        Ok(())
+    }
+
+    /// Calls [Hooks::after_delete](crate::Hooks::after_delete). Derived from the `hooks` attribute.
+    fn after_delete_hook(item: &WithId<Self>) {
+        let _ = item;
+    }
+
+    /// Returns a byte representation for the given unique index.
+    fn unique_value(&self, index: u8) -> Result<Vec<u8>> {
+        Err(Error::UnknownIndex {
+            table: Self::NAME,
+            index,
+        })
+    }
+
+    /// Returns a byte representation for the given non-unique index.
+    fn non_unique_value(&self, index: u8) -> Result<Vec<u8>> {
+        Err(Error::UnknownIndex {
+            table: Self::NAME,
+            index,
+        })
+    }
+
+    /// Storing this value in the given db returning the id.
+    ///
+    /// With hooks a copy changed by [Hooks::before_create](crate::Hooks::before_create) is stored.
+    fn create(&self, db: &Database) -> Result<Ulid> {
+        let id = Ulid::new();
+        let mut batch = WriteBatch::default();
+        let mut ctx = WriteContext {
+            db,
+            id,
+            batch: &mut batch,
+        };
+        let hooked = self.before_create_hook(&mut ctx)?;
+        let value = hooked.as_ref().unwrap_or(self);
+        let family = db.write_family::<Self>()?;
+        check_unique(db, &family, value)?;
+
+        put_entries(&mut batch, &family, id, value)?;
+
+        db.write_change(batch, || {
+            let after = to_bytes(Self::NAME, value)?;
+            Ok(Some(Record::table(Self::TABLE, id, None, Some(&after))))
+        })?;
+        value.after_create_hook(id);
         Ok(id)
     }
 
     /// Returns the value for the given id.
-    fn get(db: &DB, id: Ulid) -> Result<WithId<Self>> {
+    fn get<M: Mode>(db: &Database<M>, id: Ulid) -> Result<WithId<Self>> {
         let key = primary_key(Self::TABLE, id);
-        let bytes = db.get_pinned(key)?.ok_or(Error::NotFound)?;
-        let value = from_bytes(&bytes)?;
+        let not_found = || Error::NotFound {
+            table: Self::NAME,
+            id: Some(id),
+        };
+        let family = db.family::<Self>().ok_or_else(not_found)?;
+        let bytes = db.get_pinned_cf(&family, key)?.ok_or_else(not_found)?;
+        let (value, _) = Self::decode(&bytes)?;
         Ok(WithId { id, value })
     }
 
     /// Returns the values for the given list of ids.
-    fn get_many(db: &DB, ids: &[Ulid]) -> Result<Vec<WithId<Self>>> {
-        let keys = ids.iter().map(|id| primary_key(Self::TABLE, *id));
-        let values: Vec<_> = db
-            .multi_get(keys)
-            .into_iter()
-            .collect::<std::result::Result<_, rocksdb::Error>>()?;
+    ///
+    /// Fails with [Error::MissingIds] listing every id without a value.
+    fn get_many<M: Mode>(db: &Database<M>, ids: &[Ulid]) -> Result<Vec<WithId<Self>>> {
+        let items = Self::get_many_opt(db, ids)?;
 
-        let values: Vec<_> = values
-            .into_iter()
-            .map(|value| value.ok_or(Error::NotFound))
-            .collect::<Result<_>>()?;
+        let missing: Vec<_> = items
+            .iter()
+            .zip(ids.iter())
+            .filter(|(item, _)| item.is_none())
+            .map(|(_, &id)| id)
+            .collect();
+
+        if !missing.is_empty() {
+            return Err(Error::MissingIds {
+                table: Self::NAME,
+                ids: missing,
+            });
+        }
 
-        let items = values
+        Ok(items.into_iter().flatten().collect())
+    }
+
+    /// Returns the values for the given list of ids in the same order.
+    /// Ids without a value are returned as `None`.
+    fn get_many_opt<M: Mode>(db: &Database<M>, ids: &[Ulid]) -> Result<Vec<Option<WithId<Self>>>> {
+        let Some(family) = db.family::<Self>() else {
+            return Ok(ids.iter().map(|_| None).collect());
+        };
+        let keys = ids
+            .iter()
+            .map(|id| (&family, primary_key(Self::TABLE, *id)));
+
+        db.multi_get_cf(keys)
             .into_iter()
             .zip(ids.iter())
-            .map(|(value, &id)| from_bytes(&value).map(|value| WithId { id, value }))
-            .collect::<std::result::Result<_, ron::Error>>()?;
-
-        Ok(items)
+            .map(|(value, &id)| -> Result<_> {
+                let Some(bytes) = value? else {
+                    return Ok(None);
+                };
+                let (value, _) = Self::decode(&bytes)?;
+                Ok(Some(WithId { id, value }))
+            })
+            .collect()
     }
 
     /// Returns the value for the given unique value.
-    fn get_by_unique_index(db: &DB, index: u8, value: &[u8]) -> Result<WithId<Self>> {
+    fn get_by_unique_index<M: Mode>(
+        db: &Database<M>,
+        index: u8,
+        value: &[u8],
+    ) -> Result<WithId<Self>> {
         let key = unique_key(Self::TABLE, index, value);
-        let id = db.get_pinned(key)?.ok_or(Error::NotFound)?;
+        let not_found = || Error::NotFound {
+            table: Self::NAME,
+            id: None,
+        };
+        let family = db.family::<Self>().ok_or_else(not_found)?;
+        let id = db.get_pinned_cf(&family, key)?.ok_or_else(not_found)?;
         let id = id_from_slice(&id)?;
         Self::get(db, id)
     }
 
     /// Returns the values for the given non-unique values.
-    fn get_by_non_unique_index(db: &DB, index: u8, value: &[u8]) -> Result<Vec<WithId<Self>>> {
-        let mut prefix = key_prefix(Self::TABLE, index);
-        prefix.extend_from_slice(value);
-        let key_vals: Vec<_> = PrefixIterator::new(db, prefix).collect::<Result<_>>()?;
-        let ids: Vec<_> = key_vals
-            .into_iter()
-            .map(|(key, _)| id_from_non_unique_key(&key))
-            .collect::<Result<_>>()?;
-
+    fn get_by_non_unique_index<M: Mode>(
+        db: &Database<M>,
+        index: u8,
+        value: &[u8],
+    ) -> Result<Vec<WithId<Self>>> {
+        let ids: Vec<_> = Self::ids_by_non_unique_index(db, index, value).collect::<Result<_>>()?;
         Self::get_many(db, &ids)
     }
 
+    /// Returns an Iterator over the values for the given non-unique value.
+    fn iter_by_non_unique_index<'a, M: Mode>(
+        db: &'a Database<M>,
+        index: u8,
+        value: &[u8],
+    ) -> IndexedItems<'a, Self, M> {
+        let ids = Self::ids_by_non_unique_index(db, index, value);
+        IndexedItems::new(db, ids)
+    }
+
+    /// Returns an Iterator over the ids for the given non-unique value.
+    fn ids_by_non_unique_index<'a, M: Mode>(
+        db: &'a Database<M>,
+        index: u8,
+        value: &[u8],
+    ) -> Ids<'a> {
+        Ids::new::<Self, M>(db, non_unique_prefix(Self::TABLE, index, value))
+    }
+
+    /// Returns true if the given unique value exists.
+    fn exists_by_unique_index<M: Mode>(db: &Database<M>, index: u8, value: &[u8]) -> Result<bool> {
+        let key = unique_key(Self::TABLE, index, value);
+        let Some(family) = db.family::<Self>() else {
+            return Ok(false);
+        };
+        Ok(db.get_pinned_cf(&family, key)?.is_some())
+    }
+
+    /// Returns true if any value has the given non-unique value.
+    fn exists_by_non_unique_index<M: Mode>(
+        db: &Database<M>,
+        index: u8,
+        value: &[u8],
+    ) -> Result<bool> {
+        let id = Self::ids_by_non_unique_index(db, index, value).next();
+        Ok(id.transpose()?.is_some())
+    }
+
+    /// Returns the number of values with the given non-unique value.
+    fn count_by_non_unique_index<M: Mode>(
+        db: &Database<M>,
+        index: u8,
+        value: &[u8],
+    ) -> Result<usize> {
+        count(Self::ids_by_non_unique_index(db, index, value))
+    }
+
+    /// Returns true if a value for the given id exists.
+    fn exists<M: Mode>(db: &Database<M>, id: Ulid) -> Result<bool> {
+        let key = primary_key(Self::TABLE, id);
+        let Some(family) = db.family::<Self>() else {
+            return Ok(false);
+        };
+        Ok(db.get_pinned_cf(&family, key)?.is_some())
+    }
+
+    /// Returns the number of values of this type.
+    fn count<M: Mode>(db: &Database<M>) -> Result<usize> {
+        count(Self::ids(db))
+    }
+
+    /// Returns an Iterator over the ids of all values of this type.
+    fn ids<M: Mode>(db: &Database<M>) -> Ids<'_> {
+        Ids::new::<Self, M>(db, key_prefix(Self::TABLE, 0))
+    }
+
     /// Returns an Iterator over all values of this type.
-    fn iter(db: &DB) -> Items<Self> {
+    fn iter<M: Mode>(db: &Database<M>) -> Items<'_, Self> {
         Items::new(db)
     }
 
     /// Returns all values of this type.
-    fn all(db: &DB) -> Result<Vec<WithId<Self>>> {
+    fn all<M: Mode>(db: &Database<M>) -> Result<Vec<WithId<Self>>> {
         Self::iter(db).collect()
     }
 
     /// Updating the entry for the given id with this value.
-    fn update(&self, db: &DB, id: Ulid) -> Result<()> {
+    ///
+    /// With hooks a copy changed by [Hooks::before_update](crate::Hooks::before_update) is stored.
+    fn update(&self, db: &Database, id: Ulid) -> Result<()> {
         let previous = Self::get(db, id)?;
+        let mut batch = WriteBatch::default();
+        let mut ctx = WriteContext {
+            db,
+            id,
+            batch: &mut batch,
+        };
+        let hooked = self.before_update_hook(&previous, &mut ctx)?;
+        let value = hooked.as_ref().unwrap_or(self);
+        let family = db.write_family::<Self>()?;
         let key = primary_key(Self::TABLE, id);
 
         for index in Self::UNIQUE_INDICES {
-            let unique_val = self.unique_value(*index);
+            let unique_val = value.unique_value(*index)?;
             let key = unique_key(Self::TABLE, *index, &unique_val);
-            let value = db.get(key)?;
-            if value.is_some() && value != Some(id.as_bytes()) {
-                return Err(Error::AlreadyExists);
+            if let Some(stored) = db.get_pinned_cf(&family, key)? {
+                if *stored != id.as_bytes() {
+                    return Err(already_exists::<Self>(*index, &stored));
+                }
             }
         }
 
-        let mut batch = WriteBatch::default();
-
-        let serialized = to_bytes(self)?;
-        batch.put(key, serialized);
+        let serialized = to_bytes(Self::NAME, value)?;
+        batch.put_cf(&family, key, &serialized);
 
         for index in Self::UNIQUE_INDICES {
-            let previous_value = previous.value.unique_value(*index);
-            let new_value = self.unique_value(*index);
+            let previous_value = previous.value.unique_value(*index)?;
+            let new_value = value.unique_value(*index)?;
 
             if new_value != previous_value {
                 let previous_key = unique_key(Self::TABLE, *index, &previous_value);
                 let new_key = unique_key(Self::TABLE, *index, &new_value);
-                batch.delete(previous_key);
-                batch.put(new_key, id.as_bytes());
+                batch.delete_cf(&family, previous_key);
+                batch.put_cf(&family, new_key, id.as_bytes());
             }
         }
 
         for index in Self::NON_UNIQUE_INDICES {
-            let previous_value = previous.value.non_unique_value(*index);
-            let new_value = self.non_unique_value(*index);
+            let previous_value = previous.value.non_unique_value(*index)?;
+            let new_value = value.non_unique_value(*index)?;
 
             if new_value != previous_value {
                 let previous_key = non_unique_key(Self::TABLE, *index, &previous_value, id);
                 let new_key = non_unique_key(Self::TABLE, *index, &new_value, id);
-                batch.delete(previous_key);
-                batch.put(new_key, id.as_bytes());
+                batch.delete_cf(&family, previous_key);
+                batch.put_cf(&family, new_key, id.as_bytes());
             }
         }
 
-        db.write(batch)?;
+        db.write_change(batch, || {
+            let before = to_bytes(Self::NAME, &previous.value)?;
+            let record = Record::table(Self::TABLE, id, Some(&before), Some(&serialized));
+            Ok(Some(record))
+        })?;
+        value.after_update_hook(&previous);
         Ok(())
     }
 
     /// Delete the entry for the given id.
-    fn delete(db: &DB, id: Ulid) -> Result<()> {
+    fn delete(db: &Database, id: Ulid) -> Result<()> {
         let item = Self::get(db, id)?;
+        let mut batch = WriteBatch::default();
+        let mut ctx = WriteContext {
+            db,
+            id,
+            batch: &mut batch,
+        };
+        Self::before_delete_hook(&item, &mut ctx)?;
+        let family = db.write_family::<Self>()?;
         let key = primary_key(Self::TABLE, id);
 
-        let mut batch = WriteBatch::default();
-        batch.delete(key);
+        batch.delete_cf(&family, key);
 
         for index in Self::UNIQUE_INDICES {
-            let value = item.value.unique_value(*index);
+            let value = item.value.unique_value(*index)?;
             let key = unique_key(Self::TABLE, *index, &value);
-            batch.delete(key);
+            batch.delete_cf(&family, key);
         }
 
         for index in Self::NON_UNIQUE_INDICES {
-            let value = item.value.non_unique_value(*index);
+            let value = item.value.non_unique_value(*index)?;
             let key = non_unique_key(Self::TABLE, *index, &value, id);
-            batch.delete(key);
+            batch.delete_cf(&family, key);
         }
 
-        db.write(batch)?;
+        db.write_change(batch, || {
+            let before = to_bytes(Self::NAME, &item.value)?;
+            Ok(Some(Record::table(Self::TABLE, id, Some(&before), None)))
+        })?;
+        Self::after_delete_hook(&item);
         Ok(())
     }
+
+    /// Deletes all values and index entries of this table.
+    fn truncate(db: &Database) -> Result<()> {
+        let mut batch = WriteBatch::default();
+        let table = Self::TABLE.to_be_bytes();
+        delete_prefix_from::<Self>(db, &mut batch, &table, table.to_vec())?;
+        db.write(batch)
+    }
+
+    /// Deletes all values and index entries of this table and reclaims their disk space.
+    ///
+    /// A column family holding only this table, see
+    /// [OpenOptions::column_family_per_table](crate::OpenOptions::column_family_per_table),
+    /// is dropped entirely. Otherwise the table is truncated and its keys are compacted.
+    fn drop(db: &Database) -> Result<()> {
+        let name = db.column_family::<Self>();
+        if Self::COLUMN_FAMILY.is_none() && name != DEFAULT_COLUMN_FAMILY_NAME {
+            // the family is only created once the table is written
+            if db.family::<Self>().is_some() {
+                db.drop_cf(&name)?;
+            }
+            return db.unrecord::<Self>();
+        }
+
+        Self::truncate(db)?;
+        let family = db.write_family::<Self>()?;
+        let table = Self::TABLE.to_be_bytes();
+        db.compact_range_cf(&family, Some(&table[..]), prefix_end(&table));
+        db.unrecord::<Self>()
+    }
+
+    /// Returns a receiver of all writes made by [Table::create], [Table::update]
+    /// and [Table::delete] through this handle, delivered once they are stored.
+    ///
+    /// The subscription ends when the receiver is dropped.
+    fn subscribe(db: &Database) -> Receiver<Change<Self>>
+    where
+        Self: Send + 'static,
+    {
+        subscribe::subscribe(db, None)
+    }
+
+    /// Like [Table::subscribe], but only delivers writes where the value
+    /// before or after has the given value for the given index.
+    fn subscribe_by_index(db: &Database, index: u8, value: &[u8]) -> Result<Receiver<Change<Self>>>
+    where
+        Self: Send + 'static,
+    {
+        if !Self::UNIQUE_INDICES.contains(&index) && !Self::NON_UNIQUE_INDICES.contains(&index) {
+            return Err(Error::UnknownIndex {
+                table: Self::NAME,
+                index,
+            });
+        }
+
+        Ok(subscribe::subscribe(db, Some((index, value.to_vec()))))
+    }
+
+    /// Moves all values and index entries of this table to the table with the given number,
+    /// returning the number of moved keys. See [Table::move_to_with_progress].
+    fn move_to(db: &Database, table: u32) -> Result<usize> {
+        Self::move_to_with_progress(db, table, |_| {})
+    }
+
+    /// Moves all values and index entries of this table to the table with the given number,
+    /// calling `progress` with the number of keys moved so far after every batch.
+    ///
+    /// Every batch is written atomically. A move interrupted by a crash is resumed by
+    /// calling this again, until then the rows are split between both tables.
+    /// Fails with [Error::TableInUse] if the target holds another table.
+    /// Afterwards the `table` attribute has to be changed to the new number.
+    fn move_to_with_progress(
+        db: &Database,
+        table: u32,
+        progress: impl FnMut(usize),
+    ) -> Result<usize> {
+        relocate::move_to::<Self>(db, table, progress)
+    }
+
+    /// Rebuilds all indices from the stored values.
+    ///
+    /// Replaces every existing index entry of this table, so this can be
+    /// used after adding a `unique` or `indexed` field to a table with rows.
+    /// The new entries are built and checked for duplicates before the old ones
+    /// are replaced in a single batch, a failed rebuild leaves the indices untouched.
+    /// Replaces the catalog entry of this table, see [Database::accept_schema].
+    fn rebuild_indexes(db: &Database) -> Result<()> {
+        let indices: Vec<_> = Self::UNIQUE_INDICES
+            .iter()
+            .chain(Self::NON_UNIQUE_INDICES)
+            .copied()
+            .collect();
+        let table = Self::TABLE.to_be_bytes();
+        rebuild::<Self>(db, &indices, &table, key_prefix(Self::TABLE, 1))?;
+        db.accept_schema::<Self>()
+    }
+
+    /// Rebuilds the given index from the stored values. See [Table::rebuild_indexes].
+    fn rebuild_index(db: &Database, index: u8) -> Result<()> {
+        if !Self::UNIQUE_INDICES.contains(&index) && !Self::NON_UNIQUE_INDICES.contains(&index) {
+            return Err(Error::UnknownIndex {
+                table: Self::NAME,
+                index,
+            });
+        }
+
+        let prefix = key_prefix(Self::TABLE, index);
+        rebuild::<Self>(db, &[index], &prefix, prefix.clone())
+    }
+
+    /// Writes all values of this table with their ids to the writer, one per line.
+    /// Returns the number of written values.
+    fn export<M: Mode, W: Write>(db: &Database<M>, writer: W, format: Format) -> Result<usize> {
+        export::export::<Self, M>(db, writer, format)
+    }
+
+    /// Stores values written by [Table::export] with their original ids
+    /// and rebuilds all indices. Returns the number of imported values.
+    ///
+    /// Values with an id that is already stored are overwritten. All values are read first,
+    /// nothing is written if one of them is invalid or shares a unique value
+    /// with another one or with a stored value which is not overwritten.
+    fn import<R: BufRead>(db: &Database, reader: R, format: Format) -> Result<usize> {
+        export::import::<Self>(db, reader, format)
+    }
+
+    /// Rewrites every row stored by an older version with the current one,
+    /// replacing its index entries, and returns the number of upgraded rows. See [Table::upgrade].
+    ///
+    /// Meant to run in a background thread while the table stays in use.
+    /// Each row is written only if it is unchanged since it was read,
+    /// rows changed in between by the write methods of tables and singles are skipped.
+    /// Fails with [Error::AlreadyExists] if an upgraded value has the unique value of another row.
+    /// Replaces the catalog entry of this table afterwards, see [Database::accept_schema].
+    fn upgrade_all(db: &Database) -> Result<usize> {
+        let family = db.write_family::<Self>()?;
+        let mut upgraded = 0;
+        for item in PrefixIterator::new::<Self, ReadWrite>(db, key_prefix(Self::TABLE, 0)) {
+            let (key, bytes) = item?;
+            let (value, true) = Self::decode(&bytes)? else {
+                continue;
+            };
+
+            if write_upgraded(db, &family, id_from_primary_key(&key)?, &bytes, &value)? {
+                upgraded += 1;
+            }
+        }
+
+        db.accept_schema::<Self>()?;
+        Ok(upgraded)
+    }
+
+    /// Cross-checks the stored values against all index entries of this table,
+    /// as stored when the check started.
+    fn verify<M: Mode>(db: &Database<M>) -> Result<Report> {
+        verify::verify::<Self, M>(db)
+    }
+
+    /// Verifies this table and fixes missing and orphaned index entries.
+    ///
+    /// Duplicates and undecodable values are only reported, since
+    /// repairing them requires deciding which value to keep.
+    /// Entries changed by writes since the check are left as they are.
+    fn repair(db: &Database) -> Result<Report> {
+        let report = verify::verify::<Self, ReadWrite>(db)?;
+        verify::repair::<Self>(db, &report)?;
+        Ok(report)
+    }
 }
 
 /// Wrapper type for an entries value and the associated id.
@@ -202,26 +615,193 @@ pub struct WithId<T> {
     pub value: T,
 }
 
-pub(crate) fn to_bytes<T: Serialize>(value: &T) -> ron::Result<Vec<u8>> {
-    let text = ron::to_string(value)?;
+/// Encoding of stored values, recorded in the catalog.
+pub(crate) const CODEC: &str = "ron";
+
+pub(crate) fn to_bytes<T: Serialize>(table: &'static str, value: &T) -> Result<Vec<u8>> {
+    let text = ron::to_string(value).map_err(|source| Error::Encoding { table, source })?;
     Ok(text.into_bytes())
 }
 
-pub(crate) fn from_bytes<T: DeserializeOwned>(bytes: &[u8]) -> ron::Result<T> {
+pub(crate) fn from_bytes<T: DeserializeOwned>(table: &'static str, bytes: &[u8]) -> Result<T> {
     let text = String::from_utf8_lossy(bytes);
-    let value = ron::from_str(&text)?;
+    let value = ron::from_str(&text).map_err(|source| Error::Decoding {
+        table,
+        len: bytes.len(),
+        source,
+    })?;
     Ok(value)
 }
 
-fn check_unique<T: Table>(db: &DB, item: &T) -> Result<()> {
+/// Builds the error for a unique value already held by the entry with the stored id.
+fn already_exists<T: Table>(index: u8, stored: &[u8]) -> Error {
+    match id_from_slice(stored) {
+        Ok(id) => Error::AlreadyExists {
+            table: T::NAME,
+            index,
+            index_name: T::index_name(index),
+            id,
+        },
+        Err(err) => err,
+    }
+}
+
+/// Adds the value and all of its index entries to the batch.
+fn put_entries<T: Table>(
+    batch: &mut WriteBatch,
+    family: &Family<'_>,
+    id: Ulid,
+    value: &T,
+) -> Result<()> {
+    batch.put_cf(family, primary_key(T::TABLE, id), to_bytes(T::NAME, value)?);
+
+    for index in T::UNIQUE_INDICES {
+        let unique_val = value.unique_value(*index)?;
+        let key = unique_key(T::TABLE, *index, &unique_val);
+        batch.put_cf(family, key, id.as_bytes());
+    }
+
+    for index in T::NON_UNIQUE_INDICES {
+        let value = value.non_unique_value(*index)?;
+        let key = non_unique_key(T::TABLE, *index, &value, id);
+        batch.put_cf(family, key, vec![]);
+    }
+
+    Ok(())
+}
+
+/// Returns the keys of all index entries of the value.
+fn index_keys<T: Table>(value: &T, id: Ulid) -> Result<Vec<Vec<u8>>> {
+    let mut keys = Vec::new();
+    for index in T::UNIQUE_INDICES {
+        keys.push(unique_key(T::TABLE, *index, &value.unique_value(*index)?));
+    }
+    for index in T::NON_UNIQUE_INDICES {
+        keys.push(non_unique_key(
+            T::TABLE,
+            *index,
+            &value.non_unique_value(*index)?,
+            id,
+        ));
+    }
+
+    Ok(keys)
+}
+
+/// Replaces `bytes` stored by an older version and its index entries with the upgraded value,
+/// returning false if the stored value changed since it was read.
+fn write_upgraded<T: Table>(
+    db: &Database,
+    family: &Family<'_>,
+    id: Ulid,
+    bytes: &[u8],
+    value: &T,
+) -> Result<bool> {
+    db.write_exclusive(|| {
+        let key = primary_key(T::TABLE, id);
+        if db.get_pinned_cf(family, key)?.as_deref() != Some(bytes) {
+            return Ok(false);
+        }
+
+        for index in T::UNIQUE_INDICES {
+            let unique_val = value.unique_value(*index)?;
+            let key = unique_key(T::TABLE, *index, &unique_val);
+            if let Some(stored) = db.get_pinned_cf(family, key)? {
+                if *stored != id.as_bytes() {
+                    return Err(already_exists::<T>(*index, &stored));
+                }
+            }
+        }
+
+        // entries kept by the upgrade are deleted and put again
+        let mut batch = WriteBatch::default();
+        for key in T::stored_index_keys(bytes, id)? {
+            batch.delete_cf(family, key);
+        }
+        put_entries(&mut batch, family, id, value)?;
+        db.write(batch)?;
+        Ok(true)
+    })
+}
+
+fn check_unique<T: Table>(db: &Database, family: &Family<'_>, item: &T) -> Result<()> {
     for index in T::UNIQUE_INDICES {
-        let unique_val = item.unique_value(*index);
+        let unique_val = item.unique_value(*index)?;
         let key = unique_key(T::TABLE, *index, &unique_val);
-        let value = db.get(key)?;
-        if value.is_some() {
-            return Err(Error::AlreadyExists);
+        if let Some(stored) = db.get_pinned_cf(family, key)? {
+            return Err(already_exists::<T>(*index, &stored));
+        }
+    }
+
+    Ok(())
+}
+
+fn count(mut ids: Ids<'_>) -> Result<usize> {
+    ids.try_fold(0, |count, id| id.map(|_| count + 1))
+}
+
+/// Number of rows written per batch by migrations, imports and moves.
+pub(crate) const REBUILD_BATCH_SIZE: usize = 1024;
+
+/// Deletes all keys starting with `prefix` that are not smaller than `start`.
+fn delete_prefix_from<T: Table>(
+    db: &Database,
+    batch: &mut WriteBatch,
+    prefix: &[u8],
+    start: Vec<u8>,
+) -> Result<()> {
+    let family = db.write_family::<T>()?;
+    match prefix_end(prefix) {
+        Some(end) => batch.delete_range_cf(&family, start, end),
+        None => {
+            for item in PrefixIterator::new::<T, ReadWrite>(db, prefix.to_vec()) {
+                let (key, _) = item?;
+                if *key >= *start {
+                    batch.delete_cf(&family, key);
+                }
+            }
         }
     }
 
     Ok(())
 }
+
+/// Replaces the keys deleted by [delete_prefix_from] with entries
+/// of the given indices built from the stored values.
+///
+/// All entries are collected in one batch, so readers see either the old or the new ones.
+/// Writes of tables and singles wait until the entries are replaced,
+/// so none of their index entries are lost or written back stale.
+fn rebuild<T: Table>(db: &Database, indices: &[u8], prefix: &[u8], start: Vec<u8>) -> Result<()> {
+    db.write_exclusive(|| {
+        let family = db.write_family::<T>()?;
+        let mut entries = Vec::new();
+        let mut unique = HashMap::new();
+
+        for item in T::iter(db) {
+            let WithId { id, value } = item?;
+
+            for index in indices {
+                if T::UNIQUE_INDICES.contains(index) {
+                    let unique_val = value.unique_value(*index)?;
+                    let key = unique_key(T::TABLE, *index, &unique_val);
+                    if let Some(stored) = unique.insert(key.clone(), id) {
+                        return Err(already_exists::<T>(*index, &stored.as_bytes()));
+                    }
+                    entries.push((key, id.as_bytes().to_vec()));
+                } else {
+                    let value = value.non_unique_value(*index)?;
+                    let key = non_unique_key(T::TABLE, *index, &value, id);
+                    entries.push((key, vec![]));
+                }
+            }
+        }
+
+        let mut batch = WriteBatch::default();
+        delete_prefix_from::<T>(db, &mut batch, prefix, start)?;
+        for (key, value) in entries {
+            batch.put_cf(&family, key, value);
+        }
+        db.write(batch)
+    })
+}
@@ -0,0 +1,277 @@
+use rocksdb::{ReadOptions, WriteBatch};
+use ulid::Ulid;
+
+use crate::database::Family;
+use crate::iter::{PrefixIterator, Snapshot};
+use crate::keys::{
+    id_from_primary_key, id_from_slice, key_prefix, non_unique_key, primary_key, unique_key,
+};
+use crate::{Database, IndexValue, Key, Mode, ReadWrite, Result, Table};
+
+/// Result of cross-checking the stored values of a table against its indices.
+/// Returned by [Table::verify](crate::Table::verify).
+#[derive(Debug, Default)]
+pub struct Report {
+    /// Number of stored values.
+    pub rows: usize,
+
+    /// Ids of stored values which could not be decoded.
+    pub undecodable: Vec<Ulid>,
+
+    /// Index entries which should exist but are not stored.
+    pub missing: Vec<IndexEntry>,
+
+    /// Stored index entries which do not match any value.
+    pub orphaned: Vec<IndexEntry>,
+
+    /// Entries whose unique value is already taken by another entry.
+    pub duplicates: Vec<IndexEntry>,
+
+    /// Keys of this table which could not be parsed.
+    pub malformed: Vec<Box<[u8]>>,
+}
+
+impl Report {
+    /// Returns true if no inconsistencies were found.
+    pub fn is_consistent(&self) -> bool {
+        self.undecodable.is_empty()
+            && self.missing.is_empty()
+            && self.orphaned.is_empty()
+            && self.duplicates.is_empty()
+            && self.malformed.is_empty()
+    }
+}
+
+/// A single index entry referenced by a [Report].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexEntry {
+    /// Number of the index.
+    pub index: u8,
+
+    /// Byte representation of the indexed value.
+    pub value: Vec<u8>,
+
+    /// Id of the referenced entry.
+    pub id: Ulid,
+}
+
+impl IndexEntry {
+    fn key(&self, table: u32) -> Vec<u8> {
+        match self.index < 128 {
+            true => unique_key(table, self.index, &self.value),
+            false => non_unique_key(table, self.index, &self.value, self.id),
+        }
+    }
+}
+
+/// Checks the table as stored at one point in time, writes during the check are not reported.
+pub(crate) fn verify<T: Table, M: Mode>(db: &Database<M>) -> Result<Report> {
+    let snapshot = db.snapshot();
+    let mut options = ReadOptions::default();
+    options.set_snapshot(&snapshot);
+
+    let mut report = Report::default();
+    check_rows::<T, M>(db, &snapshot, &options, &mut report)?;
+
+    for index in 1..=u8::MAX {
+        check_index::<T, M>(db, index, &snapshot, &options, &mut report)?;
+    }
+
+    Ok(report)
+}
+
+/// Fixes the missing, orphaned and malformed entries of the report which are still inconsistent.
+///
+/// Every entry is checked again while writes of tables and singles wait,
+/// so entries fixed by writes after the report was created are left alone.
+pub(crate) fn repair<T: Table>(db: &Database, report: &Report) -> Result<()> {
+    let family = db.write_family::<T>()?;
+    let options = ReadOptions::default();
+
+    db.write_exclusive(|| {
+        let mut batch = WriteBatch::default();
+
+        for key in &report.malformed {
+            // unique keys are only malformed by their value, which may have been replaced
+            let replaced = match Key::parse(key) {
+                Ok(Key::Unique { .. }) => db
+                    .get_pinned_cf(&family, key)?
+                    .map_or(true, |bytes| id_from_slice(&bytes).is_ok()),
+                _ => false,
+            };
+            if !replaced {
+                batch.delete_cf(&family, key);
+            }
+        }
+
+        for entry in &report.orphaned {
+            let key = entry.key(T::TABLE);
+            let stored = db.get_pinned_cf(&family, &key)?;
+            let references = match entry.index < 128 {
+                true => stored.as_deref() == Some(&entry.id.as_bytes()[..]),
+                false => stored.is_some(),
+            };
+            if references && !holds::<T, ReadWrite>(db, &family, &options, entry.id, entry)? {
+                batch.delete_cf(&family, key);
+            }
+        }
+
+        for entry in &report.missing {
+            let key = entry.key(T::TABLE);
+            if db.get_pinned_cf(&family, &key)?.is_some()
+                || !holds::<T, ReadWrite>(db, &family, &options, entry.id, entry)?
+            {
+                continue;
+            }
+            match entry.index < 128 {
+                true => batch.put_cf(&family, key, entry.id.as_bytes()),
+                false => batch.put_cf(&family, key, vec![]),
+            }
+        }
+
+        db.write(batch)
+    })
+}
+
+/// Checks that every stored value is referenced by all of its index entries.
+fn check_rows<T: Table, M: Mode>(
+    db: &Database<M>,
+    snapshot: &Snapshot<'_>,
+    options: &ReadOptions,
+    report: &mut Report,
+) -> Result<()> {
+    let Some(family) = db.family::<T>() else {
+        return Ok(());
+    };
+    for item in PrefixIterator::at_snapshot::<T, M>(db, key_prefix(T::TABLE, 0), snapshot) {
+        let (key, bytes) = item?;
+        let Ok(id) = id_from_primary_key(&key) else {
+            report.malformed.push(key);
+            continue;
+        };
+
+        report.rows += 1;
+        let Ok((value, _)) = T::decode(&bytes) else {
+            report.undecodable.push(id);
+            continue;
+        };
+
+        for index in T::UNIQUE_INDICES {
+            let entry = IndexEntry {
+                index: *index,
+                value: value.unique_value(*index)?,
+                id,
+            };
+
+            match db.get_cf_opt(&family, entry.key(T::TABLE), options)? {
+                Some(stored) if stored == id.as_bytes() => {}
+                Some(stored) if is_duplicate::<T, M>(db, &family, options, &stored, &entry)? => {
+                    report.duplicates.push(entry)
+                }
+                _ => report.missing.push(entry),
+            }
+        }
+
+        for index in T::NON_UNIQUE_INDICES {
+            let entry = IndexEntry {
+                index: *index,
+                value: value.non_unique_value(*index)?,
+                id,
+            };
+
+            if db
+                .get_pinned_cf_opt(&family, entry.key(T::TABLE), options)?
+                .is_none()
+            {
+                report.missing.push(entry);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that every stored entry of the given index matches a stored value.
+fn check_index<T: Table, M: Mode>(
+    db: &Database<M>,
+    index: u8,
+    snapshot: &Snapshot<'_>,
+    options: &ReadOptions,
+    report: &mut Report,
+) -> Result<()> {
+    let Some(family) = db.family::<T>() else {
+        return Ok(());
+    };
+    for item in PrefixIterator::at_snapshot::<T, M>(db, key_prefix(T::TABLE, index), snapshot) {
+        let (key, bytes) = item?;
+        let parsed = match Key::parse(&key) {
+            Ok(Key::Unique { value, .. }) => id_from_slice(&bytes).ok().map(|id| (value, id)),
+            Ok(Key::NonUnique { value, id, .. }) => Some((value, id)),
+            _ => None,
+        };
+        let Some((value, id)) = parsed else {
+            report.malformed.push(key);
+            continue;
+        };
+
+        let entry = IndexEntry { index, value, id };
+        let matches = match db.get_pinned_cf_opt(&family, primary_key(T::TABLE, id), options)? {
+            // undecodable values are reported by `check_rows`
+            Some(bytes) => T::decode(&bytes).is_err() || holds_value::<T>(&bytes, &entry),
+            None => false,
+        };
+
+        if !matches {
+            report.orphaned.push(entry);
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns true if the entry referenced by `stored` has the same unique value.
+fn is_duplicate<T: Table, M: Mode>(
+    db: &Database<M>,
+    family: &Family<'_>,
+    options: &ReadOptions,
+    stored: &[u8],
+    entry: &IndexEntry,
+) -> Result<bool> {
+    let Ok(other) = id_from_slice(stored) else {
+        return Ok(false);
+    };
+    holds::<T, M>(db, family, options, other, entry)
+}
+
+/// Returns true if the value stored for `id` has the indexed value of the entry.
+fn holds<T: Table, M: Mode>(
+    db: &Database<M>,
+    family: &Family<'_>,
+    options: &ReadOptions,
+    id: Ulid,
+    entry: &IndexEntry,
+) -> Result<bool> {
+    match db.get_pinned_cf_opt(family, primary_key(T::TABLE, id), options)? {
+        Some(bytes) => Ok(holds_value::<T>(&bytes, entry)),
+        None => Ok(false),
+    }
+}
+
+/// Returns true if the stored bytes decode to a value with the indexed value of the entry.
+fn holds_value<T: Table>(bytes: &[u8], entry: &IndexEntry) -> bool {
+    match T::decode(bytes) {
+        Ok((item, _)) => indexed_value(&item, entry.index).as_ref() == Some(&entry.value),
+        Err(_) => false,
+    }
+}
+
+/// Returns the byte representation of the given index, if the table has this index.
+fn indexed_value<T: Table>(item: &T, index: u8) -> Option<Vec<u8>> {
+    if T::UNIQUE_INDICES.contains(&index) {
+        item.unique_value(index).ok()
+    } else if T::NON_UNIQUE_INDICES.contains(&index) {
+        item.non_unique_value(index).ok()
+    } else {
+        None
+    }
+}
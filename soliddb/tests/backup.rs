@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+use soliddb::*;
+use temp_dir::TempDir;
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Table)]
+#[solid(table = 1)]
+struct User {
+    #[solid(unique)]
+    name: String,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Table)]
+#[solid(table = 2, column_family = "events")]
+struct Event {
+    #[solid(indexed)]
+    kind: String,
+}
+
+#[test]
+fn backup_and_restore() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let backup_dir = TempDir::new()?;
+    let db = soliddb::open(dir.path().join("db"))?;
+
+    let id = User {
+        name: "pako".to_string(),
+    }
+    .create(&db)?;
+    Event {
+        kind: "login".to_string(),
+    }
+    .create(&db)?;
+
+    let first = db.backup_to(backup_dir.path())?;
+
+    User {
+        name: "other".to_string(),
+    }
+    .create(&db)?;
+    let second = db.backup_to(backup_dir.path())?;
+    assert!(second.id > first.id);
+
+    let backups = list_backups(backup_dir.path())?;
+    assert_eq!(backups, vec![first.clone(), second]);
+
+    let restored = dir.path().join("restored");
+    restore_from(backup_dir.path(), &restored)?;
+    let restored = soliddb::open(&restored)?;
+    assert_eq!(User::get(&restored, id)?.value.name, "pako");
+    assert_eq!(User::count(&restored)?, 2);
+    assert_eq!(Event::count_by_kind(&restored, &"login".to_string())?, 1);
+
+    let old = dir.path().join("old");
+    restore_backup(backup_dir.path(), first.id, &old)?;
+    let old = soliddb::open(&old)?;
+    assert_eq!(User::count(&old)?, 1);
+    assert_eq!(Event::count(&old)?, 1);
+
+    Ok(())
+}
+
+#[test]
+fn purge_backups() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let backup_dir = TempDir::new()?;
+    let db = soliddb::open(dir.path())?;
+
+    for i in 0..3 {
+        User {
+            name: format!("pako{i}"),
+        }
+        .create(&db)?;
+        db.backup_to(backup_dir.path())?;
+    }
+
+    purge_old_backups(backup_dir.path(), 1)?;
+    let backups = list_backups(backup_dir.path())?;
+    assert_eq!(backups.len(), 1);
+
+    let restored = TempDir::new()?;
+    restore_from(backup_dir.path(), restored.path())?;
+    assert_eq!(User::count(&soliddb::open(restored.path())?)?, 3);
+
+    Ok(())
+}
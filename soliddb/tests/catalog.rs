@@ -0,0 +1,109 @@
+use serde::{Deserialize, Serialize};
+use soliddb::*;
+use temp_dir::TempDir;
+
+#[derive(Debug, Serialize, Deserialize, Table)]
+#[solid(table = 1)]
+struct User {
+    #[solid(unique)]
+    name: String,
+    #[solid(indexed)]
+    age: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Table)]
+#[solid(table = 2, column_family = "events")]
+enum Event {
+    Login,
+    Logout { reason: String },
+}
+
+#[test]
+fn records_tables_on_first_write() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path())?;
+    assert_eq!(User::count(&db)?, 0);
+    assert!(db.catalog()?.is_empty());
+
+    User {
+        name: "pako".to_string(),
+        age: 21,
+    }
+    .create(&db)?;
+    Event::Login.create(&db)?;
+
+    let catalog = db.catalog()?;
+    assert_eq!(catalog.len(), 2);
+    assert_eq!(
+        catalog[0],
+        TableInfo {
+            table: 1,
+            name: "User".to_string(),
+            column_family: "default".to_string(),
+            fields: vec!["name".to_string(), "age".to_string()],
+            indices: vec![
+                IndexInfo {
+                    index: 1,
+                    field: Some("name".to_string()),
+                    unique: true,
+                },
+                IndexInfo {
+                    index: 128,
+                    field: Some("age".to_string()),
+                    unique: false,
+                },
+            ],
+            codec: "ron".to_string(),
+            fingerprint: User::FINGERPRINT,
+        }
+    );
+    assert_eq!(catalog[0].index_name(128), Some("age"));
+
+    let event = db.table_info(2)?.unwrap();
+    assert_eq!(event.name, "Event");
+    assert_eq!(event.column_family, "events");
+    assert_eq!(event.fields, ["Login", "Logout"]);
+    assert!(event.indices.is_empty());
+
+    assert_eq!(db.table_info(3)?, None);
+
+    Ok(())
+}
+
+#[test]
+fn read_only_does_not_record() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    drop(soliddb::open(dir.path())?);
+
+    let db = soliddb::open_read_only(dir.path())?;
+    assert_eq!(User::count(&db)?, 0);
+    assert!(db.catalog()?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn drop_removes_table() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path())?;
+    User {
+        name: "pako".to_string(),
+        age: 21,
+    }
+    .create(&db)?;
+
+    User::drop(&db)?;
+    assert_eq!(db.table_info(1)?, None);
+
+    // recorded again when written afterwards
+    User::count(&db)?;
+    assert_eq!(db.table_info(1)?, None);
+    User {
+        name: "pako".to_string(),
+        age: 21,
+    }
+    .create(&db)?;
+    assert_eq!(db.table_info(1)?.unwrap().name, "User");
+
+    Ok(())
+}
@@ -0,0 +1,138 @@
+use serde::{Deserialize, Serialize};
+use soliddb::*;
+use temp_dir::TempDir;
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Table)]
+#[solid(table = 1)]
+struct User {
+    #[solid(unique)]
+    name: String,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Single)]
+#[solid(single = 1)]
+struct Config {
+    limit: u32,
+}
+
+/// Largest number available to singles, right below the key prefix of the change feed.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Single)]
+#[solid(single = 0xFDFFFFFF)]
+struct Largest {
+    value: u32,
+}
+
+fn open(dir: &TempDir) -> Result<Database> {
+    OpenOptions::new().change_feed(true).open(dir.path())
+}
+
+fn user(name: &str) -> User {
+    User {
+        name: name.to_string(),
+    }
+}
+
+fn decode<T: serde::de::DeserializeOwned>(bytes: &Option<Vec<u8>>) -> anyhow::Result<T> {
+    let text = std::str::from_utf8(bytes.as_deref().unwrap_or_default())?;
+    Ok(ron::from_str(text)?)
+}
+
+#[test]
+fn records_table_writes() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = open(&dir)?;
+    let id = user("pako").create(&db)?;
+    user("mia").update(&db, id)?;
+    User::delete(&db, id)?;
+
+    let changes = db.changes_since(0).collect::<Result<Vec<_>>>()?;
+    let ops: Vec<_> = changes.iter().map(|change| change.op).collect();
+    assert_eq!(ops, [Op::Create, Op::Update, Op::Delete]);
+    assert!(changes.iter().all(|change| change.table == Some(1)));
+    assert!(changes.iter().all(|change| change.id == Some(id)));
+    assert_eq!(changes[0].seq, 1);
+    assert_eq!(changes[0].before, None);
+    assert_eq!(decode::<User>(&changes[0].after)?, user("pako"));
+    assert_eq!(decode::<User>(&changes[1].before)?, user("pako"));
+    assert_eq!(decode::<User>(&changes[1].after)?, user("mia"));
+    assert_eq!(changes[2].after, None);
+    assert_eq!(db.last_change()?, 3);
+    Ok(())
+}
+
+#[test]
+fn records_single_writes() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = open(&dir)?;
+    Config::delete(&db)?;
+    Config { limit: 1 }.put(&db)?;
+    Config { limit: 2 }.put(&db)?;
+    Config::delete(&db)?;
+    Largest { value: 3 }.put(&db)?;
+
+    let changes = db.changes_since(0).collect::<Result<Vec<_>>>()?;
+    let ops: Vec<_> = changes.iter().map(|change| change.op).collect();
+    assert_eq!(ops, [Op::Create, Op::Update, Op::Delete, Op::Create]);
+    assert_eq!(changes[0].single, Some(1));
+    assert_eq!(changes[0].id, None);
+    assert_eq!(decode::<Config>(&changes[1].after)?, Config { limit: 2 });
+    assert_eq!(changes[3].single, Some(0xFDFFFFFF));
+    Ok(())
+}
+
+#[test]
+fn continues_after_sequence_number() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    {
+        let db = open(&dir)?;
+        user("pako").create(&db)?;
+        user("mia").create(&db)?;
+    }
+
+    let db = open(&dir)?;
+    user("kai").create(&db)?;
+    let seqs = db
+        .changes_since(1)
+        .map(|change| change.map(|change| change.seq))
+        .collect::<Result<Vec<_>>>()?;
+    assert_eq!(seqs, [2, 3]);
+    assert_eq!(db.changes_since(3).count(), 0);
+    Ok(())
+}
+
+#[test]
+fn prunes_old_changes() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = OpenOptions::new()
+        .change_feed(true)
+        .change_retention(4)
+        .open(dir.path())?;
+    Largest { value: 1 }.put(&db)?;
+    for i in 0..9 {
+        user(&format!("user{i}")).create(&db)?;
+    }
+
+    // pruned every 4 writes, keeping the latest 4 events
+    let seqs = db
+        .changes_since(0)
+        .map(|change| change.map(|change| change.seq))
+        .collect::<Result<Vec<_>>>()?;
+    assert_eq!(seqs, [5, 6, 7, 8, 9, 10]);
+
+    db.prune_changes(9)?;
+    assert_eq!(db.changes_since(0).count(), 2);
+    assert_eq!(Largest::get(&db)?, Largest { value: 1 });
+    Ok(())
+}
+
+#[test]
+fn disabled_by_default() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path())?;
+    user("pako").create(&db)?;
+    Config { limit: 1 }.put(&db)?;
+
+    assert_eq!(db.changes_since(0).count(), 0);
+    assert_eq!(db.last_change()?, 0);
+    Ok(())
+}
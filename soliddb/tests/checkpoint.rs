@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+use soliddb::*;
+use temp_dir::TempDir;
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Table)]
+#[solid(table = 1)]
+struct User {
+    #[solid(unique)]
+    name: String,
+}
+
+#[test]
+fn checkpoint() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path().join("db"))?;
+    let id = User {
+        name: "pako".to_string(),
+    }
+    .create(&db)?;
+
+    let checkpoint_dir = dir.path().join("checkpoint");
+    db.checkpoint(&checkpoint_dir)?;
+    User::delete(&db, id)?;
+
+    let checkpoint = soliddb::open(&checkpoint_dir)?;
+    assert_eq!(User::get_by_name(&checkpoint, &"pako".to_string())?.id, id);
+    assert!(!User::exists(&db, id)?);
+
+    Ok(())
+}
+
+#[test]
+fn open_checkpoint() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = OpenOptions::new()
+        .column_family_per_table(true)
+        .open(dir.path().join("db"))?;
+    let id = User {
+        name: "pako".to_string(),
+    }
+    .create(&db)?;
+
+    let copy = db.open_checkpoint(dir.path().join("copy"))?;
+    assert_eq!(copy.column_family::<User>(), "table_1");
+    assert_eq!(User::get(&copy, id)?.value.name, "pako");
+
+    // both databases are independent
+    User {
+        name: "other".to_string(),
+    }
+    .create(&copy)?;
+    assert_eq!(User::count(&copy)?, 2);
+    assert_eq!(User::count(&db)?, 1);
+
+    Ok(())
+}
@@ -0,0 +1,119 @@
+use serde::{Deserialize, Serialize};
+use soliddb::*;
+use temp_dir::TempDir;
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Table)]
+#[solid(table = 1)]
+struct User {
+    #[solid(unique)]
+    name: String,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Table)]
+#[solid(table = 2, column_family = "events")]
+struct Event {
+    #[solid(indexed)]
+    kind: String,
+}
+
+fn create_events(db: &Database) -> anyhow::Result<Vec<ulid::Ulid>> {
+    let mut ids = Vec::new();
+    for kind in ["login", "login", "logout"] {
+        let event = Event {
+            kind: kind.to_string(),
+        };
+        ids.push(event.create(db)?);
+    }
+
+    Ok(ids)
+}
+
+#[test]
+fn table_in_column_family() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = OpenOptions::new()
+        .column_family_compression("events", Compression::Zstd)
+        .open(dir.path())?;
+    let ids = create_events(&db)?;
+
+    assert_eq!(db.column_family::<Event>(), "events");
+    assert_eq!(db.column_family::<User>(), "default");
+    assert!(db.cf_handle("events").is_some());
+
+    assert_eq!(Event::count(&db)?, 3);
+    assert_eq!(Event::count_by_kind(&db, &"login".to_string())?, 2);
+    assert_eq!(Event::get(&db, ids[2])?.value.kind, "logout");
+    assert!(Event::verify(&db)?.is_consistent());
+
+    // nothing is stored in the default column family
+    let mut primary_key = vec![0, 0, 0, 2, 0];
+    primary_key.extend_from_slice(&ids[0].0.to_be_bytes());
+    assert!(db.get(&primary_key)?.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn reads_do_not_create_column_families() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path())?;
+    let id = ulid::Ulid::new();
+
+    let err = Event::get(&db, id).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+    assert!(!Event::exists(&db, id)?);
+    assert_eq!(Event::get_many_opt(&db, &[id])?.len(), 1);
+    assert_eq!(Event::count(&db)?, 0);
+    assert_eq!(Event::count_by_kind(&db, &"login".to_string())?, 0);
+    assert!(Event::verify(&db)?.is_consistent());
+    assert!(db.cf_handle("events").is_none());
+
+    create_events(&db)?;
+    assert!(db.cf_handle("events").is_some());
+    Ok(())
+}
+
+#[test]
+fn reopen_column_families() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let id = {
+        let db = OpenOptions::new()
+            .column_family_per_table(true)
+            .open(dir.path())?;
+        create_events(&db)?;
+        User {
+            name: "pako".to_string(),
+        }
+        .create(&db)?
+    };
+
+    let db = OpenOptions::new()
+        .column_family_per_table(true)
+        .open(dir.path())?;
+    assert_eq!(db.column_family::<User>(), "table_1");
+    assert_eq!(db.column_family::<Event>(), "events");
+    assert_eq!(User::get_by_name(&db, &"pako".to_string())?.id, id);
+    assert_eq!(Event::count(&db)?, 3);
+
+    Ok(())
+}
+
+#[test]
+fn drop_column_family() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path())?;
+    create_events(&db)?;
+    User {
+        name: "pako".to_string(),
+    }
+    .create(&db)?;
+
+    db.drop_cf("events")?;
+    assert_eq!(Event::count(&db)?, 0);
+    assert_eq!(User::count(&db)?, 1);
+
+    create_events(&db)?;
+    assert_eq!(Event::count(&db)?, 3);
+
+    Ok(())
+}
@@ -0,0 +1,144 @@
+use serde::{Deserialize, Serialize};
+use soliddb::*;
+use temp_dir::TempDir;
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Table)]
+#[solid(table = 1)]
+struct User {
+    #[solid(unique)]
+    name: String,
+}
+
+#[test]
+fn open_with_options() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = OpenOptions::new()
+        .cache_size(1 << 20)
+        .compression(Compression::Lz4)
+        .write_buffer_size(1 << 20)
+        .max_open_files(64)
+        .sync_writes(true)
+        .use_fsync(true)
+        .bloom_filter(10.0)
+        .open(dir.path().join("db"))?;
+
+    let user = User {
+        name: "pako".to_string(),
+    };
+    let id = user.create(&db)?;
+    assert_eq!(User::get(&db, id)?.value, user);
+    assert!(!User::exists_by_name(&db, &"other".to_string())?);
+
+    Ok(())
+}
+
+#[test]
+fn open_missing() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let path = dir.path().join("db");
+
+    let err = OpenOptions::new()
+        .create_if_missing(false)
+        .open(&path)
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Internal);
+
+    Ok(())
+}
+
+#[test]
+fn open_read_only() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path())?;
+    let id = User {
+        name: "pako".to_string(),
+    }
+    .create(&db)?;
+
+    // does not need the lock held by `db`
+    let read_only = soliddb::open_read_only(dir.path())?;
+    assert_eq!(User::get(&read_only, id)?.value.name, "pako");
+    assert!(User::exists_by_name(&read_only, &"pako".to_string())?);
+    assert!(User::verify(&read_only)?.is_consistent());
+
+    Ok(())
+}
+
+#[test]
+fn open_as_secondary() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let secondary_dir = TempDir::new()?;
+    let db = soliddb::open(dir.path())?;
+    let user = User {
+        name: "pako".to_string(),
+    };
+    let id = user.create(&db)?;
+
+    let secondary_path = secondary_dir.path().join("secondary");
+    let secondary = soliddb::open_as_secondary(dir.path(), secondary_path)?;
+    assert_eq!(User::get(&secondary, id)?.value, user);
+
+    let other = User {
+        name: "other".to_string(),
+    };
+    let other_id = other.create(&db)?;
+    assert!(!User::exists(&secondary, other_id)?);
+
+    secondary.try_catch_up_with_primary()?;
+    assert_eq!(
+        User::get_by_name(&secondary, &"other".to_string())?.id,
+        other_id
+    );
+    assert_eq!(User::count(&secondary)?, 2);
+
+    Ok(())
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Table)]
+#[solid(table = 2)]
+struct Group {
+    #[solid(indexed)]
+    name: String,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Table)]
+#[solid(table = 4294967295)]
+struct Last {
+    #[solid(indexed)]
+    name: String,
+}
+
+#[test]
+fn scans_stop_at_table_boundary() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path())?;
+
+    for i in 0..3 {
+        User {
+            name: format!("pako{i}"),
+        }
+        .create(&db)?;
+        Group {
+            name: "users".to_string(),
+        }
+        .create(&db)?;
+        Last {
+            name: "users".to_string(),
+        }
+        .create(&db)?;
+    }
+
+    assert_eq!(User::count(&db)?, 3);
+    assert_eq!(Group::all(&db)?.len(), 3);
+    assert_eq!(Group::count_by_name(&db, &"users".to_string())?, 3);
+    assert_eq!(Last::all(&db)?.len(), 3);
+    assert_eq!(Last::count_by_name(&db, &"users".to_string())?, 3);
+
+    Group::rebuild_indexes(&db)?;
+    Last::rebuild_indexes(&db)?;
+    assert!(Group::verify(&db)?.is_consistent());
+    assert!(Last::verify(&db)?.is_consistent());
+    assert_eq!(User::count(&db)?, 3);
+
+    Ok(())
+}
@@ -0,0 +1,197 @@
+use serde::{Deserialize, Serialize};
+use soliddb::*;
+use temp_dir::TempDir;
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Table)]
+#[solid(table = 1)]
+struct User {
+    #[solid(unique)]
+    name: String,
+    #[solid(indexed)]
+    age: u32,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Single)]
+#[solid(single = 1)]
+struct Config {
+    version: u32,
+}
+
+fn create_users(db: &Database) -> anyhow::Result<Vec<ulid::Ulid>> {
+    let mut ids = Vec::new();
+    for (name, age) in [("pako", 21), ("dako", 21), ("nako", 30)] {
+        let user = User {
+            name: name.to_string(),
+            age,
+        };
+        ids.push(user.create(db)?);
+    }
+    Ok(ids)
+}
+
+#[test]
+fn table_round_trip() -> anyhow::Result<()> {
+    let formats = [
+        Format::Ron,
+        #[cfg(feature = "serde_json")]
+        Format::JsonLines,
+    ];
+
+    for format in formats {
+        let dir = TempDir::new()?;
+        let db = soliddb::open(dir.path().join("db"))?;
+        let ids = create_users(&db)?;
+
+        let mut exported = Vec::new();
+        assert_eq!(User::export(&db, &mut exported, format)?, 3);
+        assert_eq!(String::from_utf8(exported.clone())?.lines().count(), 3);
+
+        let target = soliddb::open(dir.path().join("target"))?;
+        assert_eq!(User::import(&target, exported.as_slice(), format)?, 3);
+
+        for id in ids {
+            assert_eq!(User::get(&target, id)?.value, User::get(&db, id)?.value);
+        }
+        assert_eq!(
+            User::get_by_name(&target, &"nako".to_string())?.value.age,
+            30
+        );
+        assert_eq!(User::count_by_age(&target, &21)?, 2);
+        assert!(User::verify(&target)?.is_consistent());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn invalid_record() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path())?;
+
+    let input = "\n(id:\"01GQ3ZAQ6Q3Y5Z7M0V5V9C2X1A\")\n";
+    let err = User::import(&db, input.as_bytes(), Format::Ron).unwrap_err();
+    assert!(matches!(err, Error::InvalidRecord { line: 2, .. }));
+    assert_eq!(err.kind(), ErrorKind::InvalidRecord);
+
+    Ok(())
+}
+
+#[test]
+fn dump_round_trip() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path().join("db"))?;
+    let ids = create_users(&db)?;
+    Config { version: 7 }.put(&db)?;
+
+    let dump = Dump::new().table::<User>().single::<Config>();
+    let mut exported = Vec::new();
+    assert_eq!(dump.export(&db, &mut exported, Format::Ron)?, 4);
+
+    let target = soliddb::open(dir.path().join("target"))?;
+    assert_eq!(dump.import(&target, exported.as_slice(), Format::Ron)?, 4);
+
+    assert_eq!(Config::get(&target)?, Config { version: 7 });
+    assert_eq!(User::count(&target)?, 3);
+    assert_eq!(User::get_by_name(&target, &"pako".to_string())?.id, ids[0]);
+    assert!(User::verify(&target)?.is_consistent());
+
+    Ok(())
+}
+
+#[test]
+fn dump_all_registered() -> anyhow::Result<()> {
+    #[derive(Serialize, Deserialize, Table)]
+    #[solid(table = 2, version = 1)]
+    struct NoteV1 {}
+
+    #[derive(Serialize, Deserialize, Table)]
+    #[solid(table = 2, version = 2)]
+    struct NoteV2 {}
+
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path().join("db"))?;
+    let ids = create_users(&db)?;
+    Config { version: 7 }.put(&db)?;
+    drop(db);
+
+    let db = soliddb::open_read_only(dir.path().join("db"))?;
+    let dump = Dump::all();
+    assert_eq!(format!("{dump:?}"), "Dump { tables: [1, 2], singles: [1] }");
+    let mut exported = Vec::new();
+    assert_eq!(dump.export(&db, &mut exported, Format::Ron)?, 4);
+
+    let target = soliddb::open(dir.path().join("target"))?;
+    assert_eq!(
+        Dump::all().import(&target, exported.as_slice(), Format::Ron)?,
+        4
+    );
+    assert_eq!(Config::get(&target)?, Config { version: 7 });
+    assert_eq!(User::get_by_name(&target, &"nako".to_string())?.id, ids[2]);
+
+    Ok(())
+}
+
+#[test]
+fn dump_unknown_record() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path().join("db"))?;
+    create_users(&db)?;
+    Config { version: 7 }.put(&db)?;
+
+    let mut exported = Vec::new();
+    Dump::new()
+        .table::<User>()
+        .single::<Config>()
+        .export(&db, &mut exported, Format::Ron)?;
+
+    let target = soliddb::open(dir.path().join("target"))?;
+    let err = Dump::new()
+        .table::<User>()
+        .import(&target, exported.as_slice(), Format::Ron)
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        Error::UnknownRecord {
+            line: 4,
+            table: None,
+            single: Some(1)
+        }
+    ));
+    assert_eq!(User::count(&target)?, 0);
+
+    Ok(())
+}
+
+#[test]
+fn import_rejects_duplicates() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path().join("db"))?;
+    let ids = create_users(&db)?;
+    let mut exported = Vec::new();
+    User::export(&db, &mut exported, Format::Ron)?;
+
+    // a stored row holding a unique value of the import
+    let target = soliddb::open(dir.path().join("target"))?;
+    let existing = User {
+        name: "dako".to_string(),
+        age: 40,
+    }
+    .create(&target)?;
+    let err = User::import(&target, exported.as_slice(), Format::Ron).unwrap_err();
+    assert!(matches!(err, Error::AlreadyExists { id, .. } if id == existing));
+    assert_eq!(User::count(&target)?, 1);
+
+    // two imported rows sharing a unique value
+    User::delete(&target, existing)?;
+    let mut duplicated = exported.clone();
+    let pako = format!(
+        "(id:\"{}\",value:(name:\"pako\",age:1))\n",
+        ulid::Ulid::new()
+    );
+    duplicated.extend_from_slice(pako.as_bytes());
+    let err = User::import(&target, duplicated.as_slice(), Format::Ron).unwrap_err();
+    assert!(matches!(err, Error::AlreadyExists { id, .. } if id == ids[0]));
+    assert_eq!(User::count(&target)?, 0);
+
+    Ok(())
+}
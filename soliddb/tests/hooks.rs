@@ -0,0 +1,159 @@
+use std::cell::RefCell;
+
+use serde::{Deserialize, Serialize};
+use soliddb::*;
+use temp_dir::TempDir;
+use ulid::Ulid;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Table)]
+#[solid(table = 1, hooks)]
+struct Post {
+    #[solid(unique)]
+    slug: String,
+    title: String,
+    revision: u32,
+    locked: bool,
+}
+
+/// Title of the last created post, written by [Post::before_create].
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Single)]
+#[solid(single = 1)]
+struct LastTitle(String);
+
+thread_local! {
+    /// Calls of the `after_*` hooks on this thread.
+    static CALLS: RefCell<Vec<&'static str>> = RefCell::default();
+}
+
+fn called(hook: &'static str) {
+    CALLS.with(|calls| calls.borrow_mut().push(hook));
+}
+
+fn calls() -> Vec<&'static str> {
+    CALLS.with(|calls| calls.take())
+}
+
+impl Hooks for Post {
+    fn before_create(&mut self, ctx: &mut WriteContext<'_>) -> Result<()> {
+        if self.title.is_empty() {
+            return Err(rejected("title is empty"));
+        }
+        self.slug = self.title.to_lowercase().replace(' ', "-");
+
+        let key = Key::Single { single: 1 }.to_bytes();
+        ctx.batch.put(key, format!("({:?})", self.title));
+        Ok(())
+    }
+
+    fn after_create(&self, _id: Ulid) {
+        called("after_create");
+    }
+
+    fn before_update(&mut self, old: &WithId<Self>, _ctx: &mut WriteContext<'_>) -> Result<()> {
+        if old.value.locked && self.locked {
+            return Err(rejected("post is locked"));
+        }
+        self.revision = old.value.revision + 1;
+        Ok(())
+    }
+
+    fn after_update(&self, _old: &WithId<Self>) {
+        called("after_update");
+    }
+
+    fn before_delete(item: &WithId<Self>, _ctx: &mut WriteContext<'_>) -> Result<()> {
+        match item.value.locked {
+            true => Err(rejected("post is locked")),
+            false => Ok(()),
+        }
+    }
+
+    fn after_delete(_item: &WithId<Self>) {
+        called("after_delete");
+    }
+}
+
+fn rejected(reason: &str) -> Error {
+    Error::Rejected {
+        table: Post::NAME,
+        reason: reason.to_string(),
+    }
+}
+
+fn post(title: &str) -> Post {
+    Post {
+        slug: String::new(),
+        title: title.to_string(),
+        revision: 0,
+        locked: false,
+    }
+}
+
+#[test]
+fn changes_values_before_writing() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path())?;
+    let id = post("Hello World").create(&db)?;
+
+    let stored = Post::get_by_slug(&db, &"hello-world".to_string())?;
+    assert_eq!(stored.id, id);
+    assert_eq!(stored.value.revision, 0);
+
+    stored.value.update(&db, id)?;
+    stored.value.update(&db, id)?;
+    assert_eq!(Post::get(&db, id)?.value.revision, 2);
+    Ok(())
+}
+
+#[test]
+fn writes_batch_of_hooks_with_value() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path())?;
+    post("Hello World").create(&db)?;
+    assert_eq!(LastTitle::get(&db)?, LastTitle("Hello World".to_string()));
+
+    // the batch is dropped with the failed write
+    let err = post("hello world").create(&db).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::AlreadyExists);
+    assert_eq!(LastTitle::get(&db)?, LastTitle("Hello World".to_string()));
+    Ok(())
+}
+
+#[test]
+fn calls_after_hooks_once_written() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path())?;
+    calls();
+
+    let id = post("Hello").create(&db)?;
+    post("hello").create(&db).unwrap_err();
+    let stored = Post::get(&db, id)?.value;
+    stored.update(&db, id)?;
+    Post::delete(&db, id)?;
+    assert_eq!(calls(), ["after_create", "after_update", "after_delete"]);
+    Ok(())
+}
+
+#[test]
+fn rejects_writes() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path())?;
+
+    let err = post("").create(&db).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Rejected);
+    assert_eq!(err.table(), Some("Post"));
+    assert_eq!(Post::count(&db)?, 0);
+
+    let id = post("Hello").create(&db)?;
+    let mut locked = Post::get(&db, id)?.value;
+    locked.locked = true;
+    locked.update(&db, id)?;
+
+    let err = locked.update(&db, id).unwrap_err();
+    assert!(matches!(err, Error::Rejected { reason, .. } if reason == "post is locked"));
+    assert_eq!(Post::get(&db, id)?.value.revision, 1);
+
+    Post::delete(&db, id).unwrap_err();
+    assert!(Post::exists(&db, id)?);
+    Ok(())
+}
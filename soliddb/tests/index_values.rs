@@ -0,0 +1,170 @@
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+use soliddb::*;
+use temp_dir::TempDir;
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, IndexValue)]
+enum Role {
+    Admin,
+    User,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, IndexValue)]
+struct Email(String);
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Table)]
+#[solid(table = 1)]
+struct User {
+    #[solid(unique)]
+    email: Email,
+    #[solid(indexed)]
+    role: Role,
+    #[solid(indexed)]
+    active: bool,
+    #[solid(unique)]
+    location: (u32, u32),
+}
+
+#[test]
+fn derived_index_values() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path())?;
+
+    let admin = User {
+        email: Email("pako@example.com".to_string()),
+        role: Role::Admin,
+        active: true,
+        location: (1, 2),
+    };
+    admin.create(&db)?;
+
+    let user = User {
+        email: Email("simon@example.com".to_string()),
+        role: Role::User,
+        active: true,
+        location: (2, 1),
+    };
+    user.create(&db)?;
+
+    let got = User::get_by_email(&db, &Email("pako@example.com".to_string()))?;
+    assert_eq!(got.value, admin);
+
+    let got = User::get_by_role(&db, &Role::User)?;
+    assert_eq!(got.len(), 1);
+    assert_eq!(got[0].value, user);
+
+    let got = User::get_by_active(&db, &true)?;
+    assert_eq!(got.len(), 2);
+
+    let got = User::get_by_location(&db, &(2, 1))?;
+    assert_eq!(got.value, user);
+
+    Ok(())
+}
+
+#[test]
+fn floats_preserve_order() {
+    let values = [
+        f64::NEG_INFINITY,
+        -1e10,
+        -1.5,
+        -0.0,
+        0.0,
+        1e-10,
+        1.5,
+        1e10,
+        f64::INFINITY,
+    ];
+
+    for pair in values.windows(2) {
+        assert!(pair[0].as_bytes() < pair[1].as_bytes(), "{pair:?}");
+    }
+}
+
+#[test]
+fn times_preserve_order() {
+    let epoch = SystemTime::UNIX_EPOCH;
+    let values = [
+        epoch - Duration::from_secs(10),
+        epoch - Duration::from_nanos(1),
+        epoch,
+        epoch + Duration::from_nanos(1),
+        epoch + Duration::from_secs(10),
+    ];
+
+    for pair in values.windows(2) {
+        assert!(pair[0].as_bytes() < pair[1].as_bytes(), "{pair:?}");
+    }
+}
+
+#[test]
+fn bytes_are_stored_raw() {
+    let bytes: &[u8] = &[1, 2, 3];
+    assert_eq!(bytes.as_bytes(), vec![1, 2, 3]);
+    assert_eq!(vec![1u8, 2, 3].as_bytes(), vec![1, 2, 3]);
+    assert_eq!([1u8, 2, 3].as_bytes(), vec![1, 2, 3]);
+}
+
+#[test]
+fn composite_values_are_unambiguous() {
+    let pairs = [("ab", "c"), ("a", "bc"), ("a\0", "c"), ("a", "\0c")];
+    for (i, a) in pairs.iter().enumerate() {
+        for b in &pairs[i + 1..] {
+            assert_ne!(a.as_bytes(), b.as_bytes(), "{a:?} {b:?}");
+        }
+    }
+
+    let lists: [&[&str]; 2] = [&["ab"], &["a", "b"]];
+    assert_ne!(lists[0].as_bytes(), lists[1].as_bytes());
+    assert_eq!((1u8, 2u16).as_bytes(), vec![1, 0, 2]);
+}
+
+#[test]
+fn vectors_keep_concatenated_elements() {
+    let list = vec!["a".to_string(), "b".to_string()];
+    assert_eq!(list.as_bytes(), b"ab".to_vec());
+    let set: std::collections::BTreeSet<_> = list.into_iter().collect();
+    assert_eq!(set.as_bytes(), b"ab".to_vec());
+}
+
+#[test]
+fn composite_values_preserve_order() {
+    let values = [
+        ("", 2u32),
+        ("a", 1),
+        ("a", 2),
+        ("a\0", 0),
+        ("ab", 0),
+        ("b", 0),
+    ];
+
+    for pair in values.windows(2) {
+        assert!(pair[0].as_bytes() < pair[1].as_bytes(), "{pair:?}");
+    }
+}
+
+#[test]
+fn unique_tuples_of_strings() -> anyhow::Result<()> {
+    #[derive(Debug, Serialize, Deserialize, Table)]
+    #[solid(table = 2)]
+    struct Member {
+        #[solid(unique = true)]
+        name: (String, String),
+    }
+
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path())?;
+    let name = |first: &str, last: &str| (first.to_string(), last.to_string());
+    Member {
+        name: name("ab", "c"),
+    }
+    .create(&db)?;
+    let id = Member {
+        name: name("a", "bc"),
+    }
+    .create(&db)?;
+
+    assert_eq!(Member::get_by_name(&db, &name("a", "bc"))?.id, id);
+    Ok(())
+}
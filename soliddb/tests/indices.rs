@@ -20,8 +20,19 @@ fn unique_is_unique() -> anyhow::Result<()> {
         name: "pako".to_string(),
         group: "users".to_string(),
     };
-    user.create(&db)?;
-    user.create(&db).unwrap_err();
+    let id = user.create(&db)?;
+    let err = user.create(&db).unwrap_err();
+
+    assert_eq!(err.kind(), ErrorKind::AlreadyExists);
+    assert_eq!(err.table(), Some("User"));
+    assert!(matches!(
+        err,
+        Error::AlreadyExists {
+            index_name: Some("name"),
+            id: existing,
+            ..
+        } if existing == id
+    ));
 
     Ok(())
 }
@@ -56,3 +67,173 @@ fn get_by_indices() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn iter_and_ids_by_indices() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path())?;
+
+    let mut ids = Vec::new();
+    for i in 0..3 {
+        let user = User {
+            name: format!("pako{i}"),
+            group: "user".to_string(),
+        };
+        ids.push(user.create(&db)?);
+    }
+
+    let user = User {
+        name: "simon".to_string(),
+        group: "users".to_string(),
+    };
+    user.create(&db)?;
+
+    let mut users = User::iter_by_group(&db, &"user".to_string());
+    let first = users.next().unwrap()?;
+    assert!(ids.contains(&first.id));
+    assert_eq!(users.count(), 2);
+
+    let mut found: Vec<_> = User::ids_by_group(&db, &"user".to_string()).collect::<Result<_>>()?;
+    found.sort();
+    ids.sort();
+    assert_eq!(found, ids);
+
+    let users = User::get_by_group(&db, &"users".to_string())?;
+    assert_eq!(users.len(), 1);
+    assert_eq!(users[0].value, user);
+
+    Ok(())
+}
+
+#[test]
+fn count_and_exists_by_indices() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path())?;
+
+    for i in 0..3 {
+        let user = User {
+            name: format!("pako{i}"),
+            group: "users".to_string(),
+        };
+        user.create(&db)?;
+    }
+
+    assert_eq!(User::count_by_group(&db, &"users".to_string())?, 3);
+    assert_eq!(User::count_by_group(&db, &"admins".to_string())?, 0);
+    assert!(User::exists_by_group(&db, &"users".to_string())?);
+    assert!(!User::exists_by_group(&db, &"user".to_string())?);
+    assert!(User::exists_by_name(&db, &"pako1".to_string())?);
+    assert!(!User::exists_by_name(&db, &"simon".to_string())?);
+
+    Ok(())
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Table)]
+#[solid(table = 2, version = 1)]
+struct Item {
+    name: String,
+    category: String,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Table)]
+#[solid(table = 2, version = 2)]
+struct IndexedItem {
+    #[solid(unique)]
+    name: String,
+    #[solid(indexed)]
+    category: String,
+}
+
+#[test]
+fn rebuild_added_indices() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path())?;
+
+    for i in 0..10 {
+        let item = Item {
+            name: format!("item{i}"),
+            category: format!("category{}", i % 2),
+        };
+        item.create(&db)?;
+    }
+
+    assert!(IndexedItem::get_by_category(&db, &"category0".to_string())?.is_empty());
+
+    IndexedItem::rebuild_indexes(&db)?;
+
+    let items = IndexedItem::get_by_category(&db, &"category0".to_string())?;
+    assert_eq!(items.len(), 5);
+
+    let item = IndexedItem::get_by_name(&db, &"item3".to_string())?;
+    assert_eq!(item.value.category, "category1");
+
+    IndexedItem::rebuild_index(&db, 128)?;
+    let items = IndexedItem::get_by_category(&db, &"category1".to_string())?;
+    assert_eq!(items.len(), 5);
+
+    Ok(())
+}
+
+#[test]
+fn rebuild_detects_duplicates() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path())?;
+
+    let mut ids = Vec::new();
+    for name in ["item", "other"] {
+        let item = IndexedItem {
+            name: name.to_string(),
+            category: "category".to_string(),
+        };
+        ids.push(item.create(&db)?);
+    }
+    Item {
+        name: "item".to_string(),
+        category: "category".to_string(),
+    }
+    .create(&db)?;
+
+    let err = IndexedItem::rebuild_indexes(&db).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::AlreadyExists);
+    let err = IndexedItem::rebuild_index(&db, 1).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::AlreadyExists);
+
+    // the previous entries are left in place
+    assert_eq!(
+        IndexedItem::get_by_name(&db, &"item".to_string())?.id,
+        ids[0]
+    );
+    assert_eq!(
+        IndexedItem::get_by_name(&db, &"other".to_string())?.id,
+        ids[1]
+    );
+    let mut by_category: Vec<_> =
+        IndexedItem::ids_by_category(&db, &"category".to_string()).collect::<Result<_>>()?;
+    by_category.sort();
+    ids.sort();
+    assert_eq!(by_category, ids);
+
+    Ok(())
+}
+
+#[test]
+fn rebuild_changed_indices_on_open() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path())?;
+    for i in 0..4 {
+        let item = Item {
+            name: format!("item{i}"),
+            category: "category".to_string(),
+        };
+        item.create(&db)?;
+    }
+    drop(db);
+
+    let db = OpenOptions::new().rebuild_indexes(true).open(dir.path())?;
+    assert_eq!(
+        IndexedItem::count_by_category(&db, &"category".to_string())?,
+        4
+    );
+    assert!(IndexedItem::exists_by_name(&db, &"item2".to_string())?);
+    Ok(())
+}
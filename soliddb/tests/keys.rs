@@ -0,0 +1,84 @@
+use proptest::prelude::*;
+use soliddb::*;
+use ulid::Ulid;
+
+#[test]
+fn parse_known_layouts() -> anyhow::Result<()> {
+    let id = Ulid::new();
+
+    let mut primary = vec![0, 0, 0, 1, 0];
+    primary.extend_from_slice(&id.0.to_be_bytes());
+    assert_eq!(Key::parse(&primary)?, Key::Primary { table: 1, id });
+
+    let unique = vec![0, 0, 0, 1, 1, b'a'];
+    assert_eq!(
+        Key::parse(&unique)?,
+        Key::Unique {
+            table: 1,
+            index: 1,
+            value: b"a".to_vec()
+        }
+    );
+
+    let mut non_unique = vec![0, 0, 0, 1, 128, b'a'];
+    non_unique.extend_from_slice(&id.0.to_be_bytes());
+    assert_eq!(
+        Key::parse(&non_unique)?,
+        Key::NonUnique {
+            table: 1,
+            index: 128,
+            value: b"a".to_vec(),
+            id
+        }
+    );
+
+    assert_eq!(
+        Key::parse(&[0, 0, 0, 0, 0, 0, 0, 2])?,
+        Key::Single { single: 2 }
+    );
+
+    assert_eq!(
+        Key::parse(&[0, 0, 0, 0, 255, 0, 0, 0, 3])?,
+        Key::Catalog { table: 3 }
+    );
+    assert_eq!(
+        Key::parse(&[0, 0, 0, 0, 255, 0, 0, 3])?,
+        Key::Single { single: 0xFF000003 }
+    );
+
+    assert_eq!(
+        Key::parse(&[0, 0, 0, 0, 254, 0, 0, 0, 0, 0, 0, 0, 4])?,
+        Key::Change { seq: 4 }
+    );
+
+    Ok(())
+}
+
+#[test]
+fn parse_malformed() {
+    let malformed: &[&[u8]] = &[
+        &[],
+        &[0, 0, 1],
+        &[0, 0, 0, 1],
+        &[0, 0, 0, 1, 0, 1, 2, 3],
+        &[0, 0, 0, 1, 128, 1, 2, 3],
+        &[0, 0, 0, 0, 0, 0, 1],
+        &[0, 0, 0, 0, 0, 0, 0, 1, 0],
+        &[0, 0, 0, 0, 255, 0, 0, 0, 1, 0],
+        &[0, 0, 0, 0, 254, 0, 0, 0, 0, 1],
+    ];
+
+    for bytes in malformed {
+        let err = Key::parse(bytes).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::MalformedKey, "{bytes:?}");
+    }
+}
+
+proptest! {
+    #[test]
+    fn parse_never_panics(bytes in proptest::collection::vec(any::<u8>(), 0..64)) {
+        if let Ok(key) = Key::parse(&bytes) {
+            prop_assert_eq!(key.to_bytes(), bytes);
+        }
+    }
+}
@@ -5,7 +5,7 @@ use soliddb::*;
 use temp_dir::TempDir;
 
 #[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Table)]
-#[solid(table = 1)]
+#[solid(table = 1, version = 1)]
 struct UserV1 {
     #[solid(unique)]
     name: String,
@@ -13,7 +13,7 @@ struct UserV1 {
 }
 
 #[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Table)]
-#[solid(table = 1)]
+#[solid(table = 1, version = 2)]
 struct UserV2 {
     #[solid(unique)]
     name: String,
@@ -43,19 +43,19 @@ fn add_optional_field_and_more() -> anyhow::Result<()> {
 }
 
 #[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Table)]
-#[solid(table = 2)]
+#[solid(table = 2, version = 1)]
 struct Type1 {
     name: String,
 }
 
 #[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Table)]
-#[solid(table = 2)]
+#[solid(table = 2, version = 2)]
 struct Type2 {
     description: String,
 }
 
 #[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Table)]
-#[solid(table = 2)]
+#[solid(table = 2, version = 3)]
 #[serde(untagged)]
 enum Migratable {
     V1(Type1),
@@ -0,0 +1,171 @@
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use soliddb::*;
+use temp_dir::TempDir;
+
+#[derive(Debug, Serialize, Deserialize, Table)]
+#[solid(table = 1, version = 1)]
+struct UserV1 {
+    #[solid(unique)]
+    name: String,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Table)]
+#[solid(table = 1, version = 2)]
+struct UserV2 {
+    #[solid(unique)]
+    first: String,
+    #[solid(indexed)]
+    last: String,
+}
+
+fn split(old: UserV1) -> UserV2 {
+    let (first, last) = old.name.split_once(' ').unwrap_or((&old.name, ""));
+    UserV2 {
+        first: first.to_string(),
+        last: last.to_string(),
+    }
+}
+
+#[test]
+fn steps_run_once() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path())?;
+    assert_eq!(schema_version(&db)?, 0);
+
+    let runs = Arc::new(AtomicUsize::new(0));
+    let migrator = |steps: usize| {
+        (0..steps).fold(Migrator::new(), |migrator, _| {
+            let runs = runs.clone();
+            migrator.step(move |_| {
+                runs.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            })
+        })
+    };
+
+    assert_eq!(migrator(2).run(&db)?, 2);
+    assert_eq!(migrator(2).run(&db)?, 2);
+    assert_eq!(runs.load(Ordering::SeqCst), 2);
+
+    assert_eq!(migrator(3).run(&db)?, 3);
+    assert_eq!(runs.load(Ordering::SeqCst), 3);
+    assert_eq!(schema_version(&db)?, 3);
+
+    let err = migrator(1).run(&db).unwrap_err();
+    assert!(matches!(
+        err,
+        Error::UnknownVersion {
+            version: 3,
+            latest: 1
+        }
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn failed_step_runs_again() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path())?;
+
+    let failing = Migrator::new().step(|_| Ok(())).step(|_| {
+        Err(Error::NotFound {
+            table: "test",
+            id: None,
+        })
+    });
+    failing.run(&db).unwrap_err();
+    assert_eq!(schema_version(&db)?, 1);
+
+    let fixed = Migrator::new().step(|_| unreachable!()).step(|_| Ok(()));
+    assert_eq!(fixed.run(&db)?, 2);
+
+    Ok(())
+}
+
+#[test]
+fn migrate_table_keeps_ids() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path())?;
+
+    let pako = UserV1 {
+        name: "pako lastname".to_string(),
+    }
+    .create(&db)?;
+    let dako = UserV1 {
+        name: "dako lastname".to_string(),
+    }
+    .create(&db)?;
+
+    Migrator::new()
+        .step(|db| {
+            migrate_table::<UserV1, UserV2>(db, split)?;
+            Ok(())
+        })
+        .run(&db)?;
+
+    assert_eq!(
+        UserV2::get(&db, pako)?.value,
+        UserV2 {
+            first: "pako".to_string(),
+            last: "lastname".to_string(),
+        }
+    );
+    assert_eq!(UserV2::get_by_first(&db, &"dako".to_string())?.id, dako);
+    assert_eq!(UserV2::count_by_last(&db, &"lastname".to_string())?, 2);
+    assert!(UserV2::verify(&db)?.is_consistent());
+
+    let info = db.table_info(1)?.unwrap();
+    assert_eq!(info.fields, ["first", "last"]);
+
+    Ok(())
+}
+
+#[test]
+fn interrupted_migrate_table_continues() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path())?;
+
+    for i in 0..1500 {
+        UserV1 {
+            name: format!("user{i} lastname"),
+        }
+        .create(&db)?;
+    }
+
+    // the first batch of 1024 rows is written before the panic
+    let mut converted = 0;
+    let interrupted = catch_unwind(AssertUnwindSafe(|| {
+        migrate_table::<UserV1, UserV2>(&db, |old| {
+            converted += 1;
+            assert!(converted <= 1100, "interrupted");
+            split(old)
+        })
+    }));
+    assert!(interrupted.is_err());
+
+    let mut converted = 0;
+    let rows = migrate_table::<UserV1, UserV2>(&db, |old| {
+        converted += 1;
+        split(old)
+    })?;
+    assert_eq!(rows, 1500);
+    assert_eq!(converted, 1500 - 1024);
+
+    assert_eq!(UserV2::count(&db)?, 1500);
+    assert_eq!(
+        UserV2::get_by_first(&db, &"user7".to_string())?.value.last,
+        "lastname"
+    );
+    assert_eq!(UserV2::count_by_last(&db, &"lastname".to_string())?, 1500);
+    assert!(UserV2::verify(&db)?.is_consistent());
+
+    // a finished migration starts over
+    assert_eq!(migrate_table::<UserV2, UserV2>(&db, |user| user)?, 1500);
+
+    Ok(())
+}
@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize};
+use soliddb::*;
+use temp_dir::TempDir;
+
+#[allow(dead_code)]
+mod shop {
+    use super::*;
+
+    #[derive(Serialize, Deserialize, Table)]
+    #[solid(table = 7)]
+    pub struct Order {}
+
+    #[derive(Serialize, Deserialize, Single)]
+    #[solid(single = 7)]
+    pub struct Config {}
+}
+
+#[allow(dead_code)]
+mod billing {
+    use super::*;
+
+    // versioned types do not collide at compile time with unversioned ones
+    #[derive(Serialize, Deserialize, Table)]
+    #[solid(table = 7, version = 2)]
+    pub struct Order {}
+}
+
+#[test]
+fn registrations() {
+    let registrations: Vec<_> = soliddb::registrations()
+        .into_iter()
+        .map(|registration| {
+            (
+                registration.claim,
+                registration.id,
+                registration.version,
+                registration.path,
+            )
+        })
+        .collect();
+
+    assert_eq!(registrations.len(), 3);
+    assert!(registrations.contains(&(Claim::Table, 7, None, "registry::shop::Order")));
+    assert!(registrations.contains(&(Claim::Table, 7, Some(2), "registry::billing::Order")));
+    assert_eq!(
+        registrations[2],
+        (Claim::Single, 7, None, "registry::shop::Config")
+    );
+}
+
+#[test]
+fn check_collisions_of_stored_numbers() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+
+    // numbers which are not stored in the database are ignored
+    let db = soliddb::open(dir.path())?;
+    shop::Config {}.put(&db)?;
+    drop(db);
+    let db = soliddb::open(dir.path())?;
+    shop::Order {}.create(&db)?;
+    drop(db);
+
+    let err = soliddb::open(dir.path()).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Collision);
+    assert!(matches!(
+        err,
+        Error::Collision {
+            claim: Claim::Table,
+            id: 7,
+            ..
+        }
+    ));
+    assert!(err.to_string().starts_with("table 7 is claimed by both"));
+
+    let err = soliddb::open_read_only(dir.path()).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Collision);
+
+    // the check can be disabled
+    OpenOptions::new()
+        .check_collisions(false)
+        .open(dir.path())?;
+    Ok(())
+}
@@ -0,0 +1,127 @@
+use serde::{Deserialize, Serialize};
+use soliddb::*;
+use temp_dir::TempDir;
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Table)]
+#[solid(table = 1)]
+struct User {
+    #[solid(unique)]
+    name: String,
+    #[solid(indexed)]
+    age: u32,
+}
+
+/// `User` after changing its table number.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Table)]
+#[solid(table = 5)]
+struct Moved {
+    #[solid(unique)]
+    name: String,
+    #[solid(indexed)]
+    age: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Table)]
+#[solid(table = 6)]
+struct Other {
+    name: String,
+}
+
+fn create_users(db: &Database, names: &[&str]) -> anyhow::Result<()> {
+    for name in names {
+        User {
+            name: name.to_string(),
+            age: 21,
+        }
+        .create(db)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn moves_values_and_indices() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path())?;
+    create_users(&db, &["pako", "mia"])?;
+    let id = User::get_by_name(&db, &"pako".to_string())?.id;
+
+    assert_eq!(User::move_to(&db, 5)?, 6);
+    assert!(db.table_info(1)?.is_none());
+    assert_eq!(User::count(&db)?, 0);
+    assert_eq!(
+        db.table_info(5)?.map(|info| info.name),
+        Some("User".to_string())
+    );
+
+    assert_eq!(Moved::count(&db)?, 2);
+    assert_eq!(Moved::get_by_name(&db, &"pako".to_string())?.id, id);
+    assert_eq!(Moved::count_by_age(&db, &21)?, 2);
+    assert!(Moved::verify(&db)?.is_consistent());
+    Ok(())
+}
+
+#[test]
+fn moves_between_column_families() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = OpenOptions::new()
+        .column_family_per_table(true)
+        .open(dir.path())?;
+    create_users(&db, &["pako"])?;
+
+    User::move_to(&db, 5)?;
+    assert_eq!(User::count(&db)?, 0);
+    assert_eq!(Moved::get_by_name(&db, &"pako".to_string())?.value.age, 21);
+    assert_eq!(db.table_info(5)?.unwrap().column_family, "table_5");
+    Ok(())
+}
+
+#[test]
+fn resumes_interrupted_move() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path())?;
+    create_users(&db, &["pako"])?;
+    User::move_to(&db, 5)?;
+
+    // rows left behind by an interrupted move
+    create_users(&db, &["mia"])?;
+    assert_eq!(User::move_to(&db, 5)?, 3);
+    assert_eq!(Moved::count(&db)?, 2);
+    assert_eq!(User::count(&db)?, 0);
+    Ok(())
+}
+
+#[test]
+fn reports_progress() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path())?;
+    let names: Vec<_> = (0..500).map(|i| format!("user{i}")).collect();
+    create_users(&db, &names.iter().map(String::as_str).collect::<Vec<_>>())?;
+
+    let mut reported = Vec::new();
+    let moved = User::move_to_with_progress(&db, 5, |moved| reported.push(moved))?;
+    assert_eq!(moved, 1500);
+    assert_eq!(reported, [1024, 1500]);
+    Ok(())
+}
+
+#[test]
+fn rejects_used_tables() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path())?;
+    create_users(&db, &["pako"])?;
+    Other {
+        name: "other".to_string(),
+    }
+    .create(&db)?;
+
+    let err = User::move_to(&db, 6).unwrap_err();
+    assert!(matches!(err, Error::TableInUse { table: 6 }));
+    assert_eq!(err.kind(), ErrorKind::TableInUse);
+    assert!(matches!(
+        User::move_to(&db, 0),
+        Err(Error::TableInUse { table: 0 })
+    ));
+    assert_eq!(User::move_to(&db, 1)?, 0);
+    assert_eq!(User::count(&db)?, 1);
+    Ok(())
+}
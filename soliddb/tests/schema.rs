@@ -0,0 +1,148 @@
+use serde::{Deserialize, Serialize};
+use soliddb::*;
+use temp_dir::TempDir;
+
+#[derive(Debug, Serialize, Deserialize, Table)]
+#[solid(table = 1, version = 1)]
+struct ItemV1 {
+    #[solid(unique)]
+    name: String,
+    color: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Table)]
+#[solid(table = 1, version = 2)]
+struct ItemV2 {
+    #[solid(unique)]
+    name: String,
+    #[solid(indexed)]
+    #[serde(default)]
+    category: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Table)]
+#[solid(table = 2, version = 1)]
+struct OrderV1 {
+    amount: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Table)]
+#[solid(table = 2, version = 2)]
+struct OrderV2 {
+    amount: u64,
+}
+
+#[test]
+fn reports_changed_tables() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path())?;
+    assert!(db.schema_diff()?.is_empty());
+
+    ItemV1 {
+        name: "chair".to_string(),
+        color: "red".to_string(),
+    }
+    .create(&db)?;
+    OrderV1 { amount: 3 }.create(&db)?;
+
+    let diffs = db.schema_diff()?;
+    assert_eq!(diffs.len(), 2);
+    assert_eq!(
+        diffs[0],
+        SchemaDiff {
+            table: 1,
+            name: "ItemV2".to_string(),
+            added_fields: vec!["category".to_string()],
+            removed_fields: vec!["color".to_string()],
+            added_indices: vec![IndexInfo {
+                index: 128,
+                field: Some("category".to_string()),
+                unique: false,
+            }],
+            removed_indices: vec![],
+            changed_codec: None,
+            changed_fingerprint: true,
+        }
+    );
+
+    // only the type of a field changed
+    assert_eq!(diffs[1].name, "OrderV2");
+    assert!(diffs[1].added_fields.is_empty());
+    assert!(diffs[1].removed_fields.is_empty());
+    assert!(diffs[1].changed_fingerprint);
+
+    // reading and writing keep the catalog entries
+    OrderV2::count(&db)?;
+    OrderV2 { amount: 4 }.create(&db)?;
+    assert_eq!(db.schema_diff()?.len(), 2);
+
+    ItemV2::rebuild_indexes(&db)?;
+    db.accept_schema::<OrderV2>()?;
+    assert!(db.schema_diff()?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn check_schema_on_open() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path())?;
+    OrderV1 { amount: 3 }.create(&db)?;
+    drop(db);
+
+    // `other::Invoice` of another database claims the number as well
+    let mut options = OpenOptions::new();
+    options.check_collisions(false);
+    let err = options
+        .clone()
+        .check_schema(true)
+        .open(dir.path())
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::SchemaMismatch);
+    assert_eq!(err.to_string(), "schema of OrderV2 changed");
+
+    let Error::SchemaMismatch { diffs } = err else {
+        unreachable!()
+    };
+    assert_eq!(diffs[0].table, 2);
+
+    // without the check the database opens with the differences
+    let db = options.open(dir.path())?;
+    assert_eq!(db.schema_changes()[0].name, "OrderV2");
+
+    Ok(())
+}
+
+#[test]
+fn fingerprint_depends_on_layout() {
+    assert_ne!(OrderV1::FINGERPRINT, OrderV2::FINGERPRINT);
+    assert_ne!(ItemV1::FINGERPRINT, ItemV2::FINGERPRINT);
+    assert_ne!(OrderV1::FINGERPRINT, 0);
+}
+
+mod other {
+    use serde::{Deserialize, Serialize};
+    use soliddb::Table;
+
+    /// Table of another database sharing the number of [super::OrderV1].
+    #[derive(Debug, Serialize, Deserialize, Table)]
+    #[solid(table = 2)]
+    pub struct Invoice {
+        pub total: u64,
+        pub paid: bool,
+    }
+}
+
+#[test]
+fn compares_the_stored_type() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path())?;
+    other::Invoice {
+        total: 3,
+        paid: false,
+    }
+    .create(&db)?;
+    assert!(db.schema_diff()?.is_empty());
+
+    Ok(())
+}
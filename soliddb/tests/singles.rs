@@ -55,3 +55,23 @@ fn overwrite() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn implemented_without_name() -> anyhow::Result<()> {
+    #[derive(Debug, Serialize, Deserialize)]
+    struct Counter(u64);
+
+    impl Single for Counter {
+        const SINGLE: u32 = 2;
+    }
+
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path())?;
+
+    let err = Counter::get(&db).unwrap_err();
+    assert_eq!(err.table(), Some("unnamed single"));
+
+    Counter(1).put(&db)?;
+    assert_eq!(Counter::get(&db)?.0, 1);
+    Ok(())
+}
@@ -0,0 +1,133 @@
+use serde::{Deserialize, Serialize};
+use soliddb::*;
+use temp_dir::TempDir;
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Table)]
+#[solid(table = 1)]
+struct User {
+    #[solid(unique)]
+    name: String,
+    #[solid(indexed)]
+    team: String,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Table)]
+#[solid(table = 2)]
+struct Post {
+    title: String,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Single)]
+#[solid(single = 1)]
+struct Config {
+    limit: u32,
+}
+
+fn user(name: &str, team: &str) -> User {
+    User {
+        name: name.to_string(),
+        team: team.to_string(),
+    }
+}
+
+#[test]
+fn delivers_table_changes() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path())?;
+    let changes = User::subscribe(&db);
+
+    let id = user("pako", "red").create(&db)?;
+    Post {
+        title: "hello".to_string(),
+    }
+    .create(&db)?;
+    user("pako", "blue").update(&db, id)?;
+    User::delete(&db, id)?;
+
+    let received: Vec<_> = changes.try_iter().collect();
+    assert_eq!(received.len(), 3);
+    assert!(received.iter().all(|change| change.id() == id));
+    assert!(matches!(&received[0], Change::Created(item) if item.value == user("pako", "red")));
+    assert!(matches!(
+        &received[1],
+        Change::Updated { old, new } if old.value.team == "red" && new.value.team == "blue"
+    ));
+    assert!(matches!(&received[2], Change::Deleted(item) if item.value.team == "blue"));
+    Ok(())
+}
+
+#[test]
+fn filters_by_index_value() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path())?;
+    let red = User::subscribe_by_index(&db, 128, b"red")?;
+
+    let id = user("pako", "red").create(&db)?;
+    user("mia", "blue").create(&db)?;
+    user("pako", "blue").update(&db, id)?;
+
+    let received: Vec<_> = red.try_iter().collect();
+    assert_eq!(received.len(), 2);
+    assert!(matches!(&received[0], Change::Created(_)));
+    assert!(matches!(&received[1], Change::Updated { .. }));
+
+    let err = User::subscribe_by_index(&db, 2, b"red").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnknownIndex);
+    Ok(())
+}
+
+#[test]
+fn delivers_single_changes() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path())?;
+    let changes = Config::watch(&db);
+
+    Config { limit: 1 }.put(&db)?;
+    Config { limit: 2 }.put(&db)?;
+    Config::delete(&db)?;
+    Config::delete(&db)?;
+
+    let received: Vec<_> = changes.try_iter().collect();
+    assert_eq!(received.len(), 3);
+    assert!(matches!(
+        received[0],
+        SingleChange::Created(Config { limit: 1 })
+    ));
+    assert!(matches!(
+        received[1],
+        SingleChange::Updated {
+            old: Config { limit: 1 },
+            new: Config { limit: 2 }
+        }
+    ));
+    assert!(matches!(
+        received[2],
+        SingleChange::Deleted(Config { limit: 2 })
+    ));
+    Ok(())
+}
+
+#[test]
+fn ends_when_receiver_is_dropped() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = OpenOptions::new().change_feed(true).open(dir.path())?;
+    drop(User::subscribe(&db));
+
+    user("pako", "red").create(&db)?;
+    user("mia", "red").create(&db)?;
+    assert_eq!(db.last_change()?, 2);
+    Ok(())
+}
+
+#[test]
+fn delivers_across_threads() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path())?;
+    let changes = User::subscribe(&db);
+
+    let listener = std::thread::spawn(move || changes.iter().take(2).count());
+    user("pako", "red").create(&db)?;
+    user("mia", "red").create(&db)?;
+    assert_eq!(listener.join().unwrap(), 2);
+    Ok(())
+}
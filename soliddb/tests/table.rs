@@ -48,7 +48,7 @@ fn get_and_delete() -> anyhow::Result<()> {
 
     User::delete(&db, id)?;
     let err = User::get(&db, id).unwrap_err();
-    assert!(matches!(err, Error::NotFound));
+    assert!(matches!(err, Error::NotFound { table: "User", id: Some(missing) } if missing == id));
 
     Ok(())
 }
@@ -135,7 +135,35 @@ fn get_many_non_existing() -> anyhow::Result<()> {
     let non_existent = Ulid::new();
 
     let err = User::get_many(&db, &[id1, non_existent, id2, id3]).unwrap_err();
-    assert!(matches!(err, Error::NotFound));
+    assert!(matches!(err, Error::MissingIds { ids, .. } if ids == [non_existent]));
+
+    Ok(())
+}
+
+#[test]
+fn get_many_opt() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path())?;
+
+    let user1 = User {
+        name: "pako".to_string(),
+        pass: "123".to_string(),
+    };
+
+    let user2 = User {
+        name: "simon".to_string(),
+        pass: "123".to_string(),
+    };
+
+    let id1 = user1.create(&db)?;
+    let id2 = user2.create(&db)?;
+    let non_existent = Ulid::new();
+
+    let users = User::get_many_opt(&db, &[id2, non_existent, id1])?;
+    assert_eq!(users.len(), 3);
+    assert_eq!(users[0].as_ref().unwrap().value, user2);
+    assert!(users[1].is_none());
+    assert_eq!(users[2].as_ref().unwrap().value, user1);
 
     Ok(())
 }
@@ -184,3 +212,110 @@ fn iter_many() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn count_and_exists() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path())?;
+
+    assert_eq!(User::count(&db)?, 0);
+
+    let mut ids = Vec::new();
+    for i in 0..3 {
+        let user = User {
+            name: format!("pako{i}"),
+            pass: "123".to_string(),
+        };
+        ids.push(user.create(&db)?);
+    }
+
+    assert_eq!(User::count(&db)?, 3);
+    assert!(User::exists(&db, ids[0])?);
+    assert!(!User::exists(&db, Ulid::new())?);
+
+    let mut all: Vec<_> = User::ids(&db).collect::<Result<_>>()?;
+    all.sort();
+    ids.sort();
+    assert_eq!(all, ids);
+
+    User::delete(&db, ids[1])?;
+    assert_eq!(User::count(&db)?, 2);
+    assert!(!User::exists(&db, ids[1])?);
+
+    Ok(())
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Table)]
+#[solid(table = 2)]
+struct Session {
+    #[solid(unique)]
+    token: String,
+    #[solid(indexed)]
+    user: String,
+}
+
+fn create_rows(db: &Database) -> anyhow::Result<()> {
+    for i in 0..3 {
+        User {
+            name: format!("pako{i}"),
+            pass: "123".to_string(),
+        }
+        .create(db)?;
+        Session {
+            token: format!("token{i}"),
+            user: "pako".to_string(),
+        }
+        .create(db)?;
+    }
+
+    Ok(())
+}
+
+#[test]
+fn truncate() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path())?;
+    create_rows(&db)?;
+
+    Session::truncate(&db)?;
+    assert_eq!(Session::count(&db)?, 0);
+    assert!(!Session::exists_by_token(&db, &"token0".to_string())?);
+    assert!(!Session::exists_by_user(&db, &"pako".to_string())?);
+    assert_eq!(User::count(&db)?, 3);
+
+    // unique values can be reused after truncating
+    create_rows(&db)?;
+    assert_eq!(Session::count(&db)?, 3);
+    assert!(Session::verify(&db)?.is_consistent());
+
+    Ok(())
+}
+
+#[test]
+fn drop() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path())?;
+    create_rows(&db)?;
+
+    User::drop(&db)?;
+    assert_eq!(User::count(&db)?, 0);
+    assert_eq!(Session::count(&db)?, 3);
+
+    let dir = TempDir::new()?;
+    let db = OpenOptions::new()
+        .column_family_per_table(true)
+        .open(dir.path())?;
+    create_rows(&db)?;
+
+    Session::drop(&db)?;
+    assert!(db.cf_handle("table_2").is_none());
+    assert_eq!(Session::count(&db)?, 0);
+    assert!(!Session::exists_by_token(&db, &"token0".to_string())?);
+    assert_eq!(User::count(&db)?, 3);
+
+    Session::drop(&db)?;
+    User::drop(&db)?;
+    assert_eq!(User::count(&db)?, 0);
+
+    Ok(())
+}
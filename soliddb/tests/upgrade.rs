@@ -0,0 +1,159 @@
+use serde::{Deserialize, Serialize};
+use soliddb::*;
+use temp_dir::TempDir;
+
+#[derive(Debug, Serialize, Deserialize, Table)]
+#[solid(table = 1, version = 1)]
+struct UserV1 {
+    #[solid(unique)]
+    name: String,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Table)]
+#[solid(table = 1, version = 2, upgrade_from = "UserV1")]
+struct UserV2 {
+    #[solid(unique)]
+    first: String,
+    last: String,
+}
+
+impl From<UserV1> for UserV2 {
+    fn from(old: UserV1) -> Self {
+        let (first, last) = old.name.split_once(' ').unwrap_or((&old.name, ""));
+        UserV2 {
+            first: first.to_string(),
+            last: last.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Table)]
+#[solid(table = 1, version = 3, upgrade_from = "UserV2")]
+struct UserV3 {
+    #[solid(unique)]
+    first: String,
+    last: String,
+    admin: bool,
+}
+
+impl From<UserV2> for UserV3 {
+    fn from(old: UserV2) -> Self {
+        UserV3 {
+            first: old.first,
+            last: old.last,
+            admin: false,
+        }
+    }
+}
+
+fn stored(db: &Database, id: ulid::Ulid) -> anyhow::Result<Vec<u8>> {
+    let key = Key::Primary { table: 1, id }.to_bytes();
+    Ok(db.get(key)?.unwrap())
+}
+
+#[test]
+fn upgrades_on_read() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path())?;
+
+    let id = UserV1 {
+        name: "pako lastname".to_string(),
+    }
+    .create(&db)?;
+    let before = stored(&db, id)?;
+
+    let expected = UserV2 {
+        first: "pako".to_string(),
+        last: "lastname".to_string(),
+    };
+    assert_eq!(UserV2::get(&db, id)?.value, expected);
+    assert_eq!(UserV2::get_many(&db, &[id])?[0].value, expected);
+    assert_eq!(UserV2::all(&db)?[0].value, expected);
+    assert_eq!(UserV2::decode(&before)?, (expected, true));
+
+    // reads leave the stored row alone
+    assert_eq!(stored(&db, id)?, before);
+
+    Ok(())
+}
+
+#[test]
+fn upgrades_through_versions() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path())?;
+
+    let id = UserV1 {
+        name: "pako lastname".to_string(),
+    }
+    .create(&db)?;
+
+    let read_only = soliddb::open_read_only(dir.path())?;
+    assert!(!UserV3::get(&read_only, id)?.value.admin);
+    assert_eq!(UserV3::get(&db, id)?.value.first, "pako");
+    assert!(UserV3::decode(&stored(&db, id)?)?.1);
+
+    assert_eq!(UserV3::upgrade_all(&db)?, 1);
+    assert!(!UserV3::decode(&stored(&db, id)?)?.1);
+    assert_eq!(UserV3::get_by_first(&db, &"pako".to_string())?.id, id);
+    assert!(UserV3::verify(&db)?.is_consistent());
+
+    Ok(())
+}
+
+#[test]
+fn upgrade_all() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path())?;
+
+    for name in ["pako lastname", "dako lastname"] {
+        UserV1 {
+            name: name.to_string(),
+        }
+        .create(&db)?;
+    }
+    let current = UserV2 {
+        first: "nako".to_string(),
+        last: "lastname".to_string(),
+    }
+    .create(&db)?;
+
+    assert_eq!(UserV2::upgrade_all(&db)?, 2);
+    assert_eq!(UserV2::upgrade_all(&db)?, 0);
+
+    for item in UserV2::iter(&db) {
+        let item = item?;
+        assert!(!UserV2::decode(&stored(&db, item.id)?)?.1);
+    }
+    assert_eq!(UserV2::get_by_first(&db, &"nako".to_string())?.id, current);
+    assert_eq!(
+        UserV2::get_by_first(&db, &"dako".to_string())?.value.last,
+        "lastname"
+    );
+
+    // index entries of the older version are replaced
+    assert!(!UserV1::exists_by_name(&db, &"pako lastname".to_string())?);
+    assert!(UserV2::verify(&db)?.is_consistent());
+
+    Ok(())
+}
+
+#[test]
+fn upgrade_all_checks_unique_values() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path())?;
+
+    let mut ids = Vec::new();
+    for name in ["pako lastname", "pako othername"] {
+        let user = UserV1 {
+            name: name.to_string(),
+        };
+        ids.push(user.create(&db)?);
+    }
+    ids.sort();
+
+    let err = UserV2::upgrade_all(&db).unwrap_err();
+    assert!(matches!(err, Error::AlreadyExists { id, .. } if id == ids[0]));
+    assert!(UserV2::decode(&stored(&db, ids[1])?)?.1);
+
+    Ok(())
+}
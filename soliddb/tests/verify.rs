@@ -0,0 +1,91 @@
+use serde::{Deserialize, Serialize};
+use soliddb::*;
+use temp_dir::TempDir;
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Table)]
+#[solid(table = 1)]
+struct User {
+    #[solid(unique)]
+    name: String,
+    #[solid(indexed)]
+    group: String,
+}
+
+fn create_users(db: &Database) -> anyhow::Result<()> {
+    for i in 0..5 {
+        let user = User {
+            name: format!("pako{i}"),
+            group: "users".to_string(),
+        };
+        user.create(db)?;
+    }
+
+    Ok(())
+}
+
+#[test]
+fn consistent_table() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path())?;
+    create_users(&db)?;
+
+    let report = User::verify(&db)?;
+    assert_eq!(report.rows, 5);
+    assert!(report.is_consistent());
+
+    Ok(())
+}
+
+#[test]
+fn detect_and_repair() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path())?;
+    create_users(&db)?;
+
+    let user = User::get_by_name(&db, &"pako0".to_string())?;
+
+    // drop the unique entry of an existing user
+    let mut unique_key = vec![0, 0, 0, 1, 1];
+    unique_key.extend_from_slice(b"pako0");
+    db.delete(&unique_key)?;
+
+    // add a non-unique entry for a value the user does not have
+    let mut orphan_key = vec![0, 0, 0, 1, 128];
+    orphan_key.extend_from_slice(b"admins");
+    orphan_key.extend_from_slice(&user.id.0.to_be_bytes());
+    db.put(&orphan_key, [])?;
+
+    let report = User::verify(&db)?;
+    assert_eq!(report.missing.len(), 1);
+    assert_eq!(report.missing[0].id, user.id);
+    assert_eq!(report.orphaned.len(), 1);
+    assert_eq!(report.orphaned[0].value, b"admins");
+    assert!(!report.is_consistent());
+
+    User::repair(&db)?;
+
+    assert!(User::verify(&db)?.is_consistent());
+    assert_eq!(User::get_by_name(&db, &"pako0".to_string())?.id, user.id);
+    assert_eq!(User::get_by_group(&db, &"admins".to_string())?.len(), 0);
+
+    Ok(())
+}
+
+#[test]
+fn detect_undecodable() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let db = soliddb::open(dir.path())?;
+    create_users(&db)?;
+
+    let user = User::get_by_name(&db, &"pako1".to_string())?;
+    let mut primary_key = vec![0, 0, 0, 1, 0];
+    primary_key.extend_from_slice(&user.id.0.to_be_bytes());
+    db.put(&primary_key, b"not ron")?;
+
+    let report = User::verify(&db)?;
+    assert_eq!(report.undecodable, vec![user.id]);
+    assert!(report.missing.is_empty());
+    assert!(report.orphaned.is_empty());
+
+    Ok(())
+}